@@ -0,0 +1,21 @@
+# direnv stdlib extension for cred.
+#
+# Install:  cred direnv --stdlib > "${XDG_CONFIG_HOME:-$HOME/.config}/direnv/lib/use_cred.sh"
+# Then put `use cred` (or `use cred <environment>`) in a project's .envrc.
+#
+# Secrets are loaded into the environment on `cd` and reloaded whenever a vault file or
+# project.toml changes; nothing is written to disk.
+
+use_cred() {
+  local cred_env="${1:-}"
+  if ! has cred; then
+    log_error "use cred: cred is not installed"
+    return 1
+  fi
+  local script
+  if ! script="$(cred --non-interactive ${cred_env:+--env "$cred_env"} direnv)"; then
+    log_error "use cred: failed to load secrets${cred_env:+ for environment '$cred_env'}"
+    return 1
+  fi
+  eval "$script"
+}
@@ -1,6 +1,10 @@
 //! CLI argument and command definitions for cred.
 //! Parsed once in `main` and dispatched to command handlers.
 
+use crate::formats::{self, FileFormat};
+use crate::generate::{Charset, Kind};
+use crate::importers::ImportSource;
+use crate::shell::Shell;
 use crate::targets::Target;
 use crate::vault::SecretFormat;
 use clap::{Args, Parser, Subcommand};
@@ -12,7 +16,6 @@ pub struct CliFlags {
     pub non_interactive: bool,
     pub dry_run: bool,
     pub yes: bool,
-    pub no_color: bool,
 }
 
 #[derive(Parser)]
@@ -31,6 +34,9 @@ pub struct Cli {
     /// Confirm destructive actions (required for deletes)
     #[arg(long, short = 'y', global = true)]
     pub yes: bool,
+    /// Environment to operate on (defaults to the base vault)
+    #[arg(long, global = true, env = "CRED_ENV")]
+    pub env: Option<String>,
 
     #[command(subcommand)]
     pub command: Commands,
@@ -39,10 +45,18 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// Initialize a new cred project in the current directory
-    Init,
+    Init {
+        /// Protect the key with a passphrase (.cred/master.key) instead of the OS keyring
+        #[arg(long)]
+        passphrase: bool,
+    },
 
     /// Run health checks (use --json for machine output)
-    Doctor,
+    Doctor {
+        /// Restrict loose file permissions and untrack `.cred/` files from git
+        #[arg(long)]
+        fix: bool,
+    },
 
     /// Manage global target authentication
     Target {
@@ -56,18 +70,37 @@ pub enum Commands {
         action: SecretAction,
     },
 
-    /// Import secrets from a .env file into the local vault
+    /// Import secrets from a .env file, password manager or secrets service
     Import(ImportArgs),
 
     /// Export vault secrets to a .env file
     Export(ExportArgs),
 
+    /// Run a command with vault secrets in its environment (nothing written to disk)
+    Run(RunArgs),
+
+    /// Print shell statements exporting vault secrets, for `eval "$(cred env)"`
+    Env(EnvArgs),
+
+    /// Render a template, replacing {{ cred "KEY" }} placeholders with vault values
+    Render(RenderArgs),
+
+    /// Print direnv statements (file watches + exports) for `.envrc`
+    Direnv {
+        /// Print the `use cred` stdlib extension instead
+        #[arg(long)]
+        stdlib: bool,
+    },
+
     /// Upload (Push) secrets to a remote hosting target (e.g. GitHub)
     Push(PushArgs),
 
     /// Atomic Delete: Removes secrets from the Remote Target AND Local Vault.
     Prune(PruneArgs),
 
+    /// Regenerate secrets from their rotation policies (see `secret generate --rotate-every`)
+    Rotate(RotateArgs),
+
     /// Inspect and modify cred global configuration (non-secret)
     Config {
         #[command(subcommand)]
@@ -79,6 +112,24 @@ pub enum Commands {
         #[command(subcommand)]
         action: ProjectAction,
     },
+
+    /// Manage the project master key
+    Key {
+        #[command(subcommand)]
+        action: KeyAction,
+    },
+
+    /// Share the vault with teammates via their X25519 public keys
+    Member {
+        #[command(subcommand)]
+        action: MemberAction,
+    },
+
+    /// Hand secrets to someone else in an encrypted, portable bundle
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -90,9 +141,84 @@ pub struct PushArgs {
     #[arg(num_args = 0..)]
     pub keys: Vec<String>,
 
+    /// Push only the keys in this scope (from project.toml)
+    #[arg(long, conflicts_with = "keys")]
+    pub scope: Option<String>,
+
     /// Explicit repository (required if not in git for GitHub)
     #[arg(long)]
     pub repo: Option<String>,
+
+    /// Push values that have passed their expiry date anyway
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct RotateArgs {
+    /// Secrets to rotate now, whether or not they are due
+    #[arg(num_args = 0.., required_unless_present = "due", conflicts_with = "due")]
+    pub keys: Vec<String>,
+
+    /// Rotate every secret whose policy is overdue
+    #[arg(long)]
+    pub due: bool,
+
+    /// Push the rotated values to this target afterwards (repeatable)
+    #[arg(long = "push", value_name = "TARGET")]
+    pub push: Vec<Target>,
+
+    /// Explicit repository for --push (required if not in git for GitHub)
+    #[arg(long, requires = "push")]
+    pub repo: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// Only inject these keys (comma-separated or repeated)
+    #[arg(long, value_delimiter = ',', conflicts_with = "scope")]
+    pub only: Vec<String>,
+
+    /// Only inject the keys in this scope (from project.toml)
+    #[arg(long)]
+    pub scope: Option<String>,
+
+    /// Also write PEM/multiline secrets to temp files, exposed as <KEY>_FILE
+    #[arg(long)]
+    pub files: bool,
+
+    /// The command to run, after `--`
+    #[arg(last = true, required = true, value_name = "COMMAND")]
+    pub command: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvArgs {
+    /// Shell dialect (detected from $SHELL if omitted)
+    #[arg(long, value_enum)]
+    pub shell: Option<Shell>,
+
+    /// Only export these keys (comma-separated or repeated)
+    #[arg(long, value_delimiter = ',', conflicts_with = "scope")]
+    pub only: Vec<String>,
+
+    /// Only export the keys in this scope (from project.toml)
+    #[arg(long)]
+    pub scope: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct RenderArgs {
+    /// Template file ("-" reads stdin)
+    pub template: String,
+
+    /// File to write (prints to stdout if omitted); created owner-only
+    #[arg(long, short = 'o')]
+    pub output: Option<String>,
+
+    /// Overwrite the output file if it exists
+    #[arg(long)]
+    pub force: bool,
 }
 
 #[derive(Args, Debug)]
@@ -129,6 +255,129 @@ pub enum ConfigAction {
 pub enum ProjectAction {
     /// Show project status (git/vault/targets), JSON recommended
     Status,
+    /// Manage named environments (e.g. dev/staging/prod)
+    Environment {
+        #[command(subcommand)]
+        action: EnvironmentAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeyAction {
+    /// Generate a new master key and re-encrypt every vault file with it
+    Rotate,
+    /// Write a passphrase-encrypted recovery copy of the master key
+    Export {
+        /// File to write (prints to stdout if omitted)
+        #[arg(long, short = 'o')]
+        output: Option<String>,
+        /// Grouped hex suitable for printing on paper or encoding as a QR code
+        #[arg(long)]
+        paper: bool,
+    },
+    /// Restore the master key into the keyring from a recovery copy
+    Import {
+        /// Recovery file written by `cred key export` ("-" reads stdin)
+        path: String,
+        /// Project the key belongs to (defaults to the current project)
+        #[arg(long)]
+        project_id: Option<uuid::Uuid>,
+    },
+    /// Split the master key into Shamir shares
+    Split {
+        /// Number of shares to create
+        #[arg(long, default_value_t = 5)]
+        shares: u8,
+        /// Number of shares needed to recover the key
+        #[arg(long, default_value_t = 3)]
+        threshold: u8,
+        /// Write one file per share into this directory (prints to stdout if omitted)
+        #[arg(long, short = 'o')]
+        output_dir: Option<String>,
+        /// Grouped hex suitable for printing on paper or encoding as a QR code
+        #[arg(long)]
+        paper: bool,
+    },
+    /// Reconstruct the master key from shares into the keyring
+    Combine {
+        /// Share files (each may hold one or more shares; "-" reads stdin)
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Project the key belongs to (defaults to the current project)
+        #[arg(long)]
+        project_id: Option<uuid::Uuid>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BundleAction {
+    /// Encrypt secrets (with their metadata) into a bundle file
+    Create {
+        /// Recipient public keys or member names (repeatable)
+        #[arg(
+            long,
+            required_unless_present = "passphrase",
+            conflicts_with = "passphrase"
+        )]
+        to: Vec<String>,
+        /// Protect the bundle with a passphrase instead (CRED_BUNDLE_PASSPHRASE or prompt)
+        #[arg(long)]
+        passphrase: bool,
+        /// File to write
+        #[arg(long, short = 'o')]
+        output: String,
+        /// Only bundle these keys (comma-separated or repeated)
+        #[arg(long, value_delimiter = ',', conflicts_with = "scope")]
+        only: Vec<String>,
+        /// Only bundle the keys in this scope (from project.toml)
+        #[arg(long)]
+        scope: Option<String>,
+        /// Overwrite the output file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Decrypt a bundle and merge it into the vault
+    Open {
+        /// Bundle file written by `cred bundle create`
+        path: String,
+        /// Overwrite existing keys instead of skipping
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MemberAction {
+    /// Show your public key (creating your identity on first use)
+    Identity,
+    /// List members who can decrypt the vault
+    List,
+    /// Add a member and re-wrap the project key for all members
+    Add {
+        name: String,
+        /// The member's public key (from `cred member identity`)
+        public_key: String,
+    },
+    /// Remove a member and re-wrap the project key for the rest
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum EnvironmentAction {
+    /// List environments declared in project.toml
+    List,
+    /// Declare a new environment with its own vault file
+    Add {
+        name: String,
+        /// Environment to inherit values from (defaults to base)
+        #[arg(long)]
+        inherits: Option<String>,
+        /// Bind to a remote environment per target, e.g. github=production
+        #[arg(long = "bind", value_name = "TARGET=ENV")]
+        bindings: Vec<String>,
+    },
+    /// Remove an environment and delete its vault file
+    Remove { name: String },
 }
 
 #[derive(Subcommand, Debug)]
@@ -152,27 +401,102 @@ pub struct SetTargetArgs {
 
 #[derive(Subcommand, Debug)]
 pub enum SecretAction {
-    /// Set a secret value (with optional metadata)
+    /// Set a secret value (with optional metadata); prompts for it if no source is given
     Set {
         key: String,
-        value: String,
+        /// The value (visible in shell history and `ps`; prefer --stdin, --from-file or the prompt)
+        #[arg(conflicts_with_all = ["stdin", "from_file"])]
+        value: Option<String>,
+        /// Read the value from stdin
+        #[arg(long, conflicts_with = "from_file")]
+        stdin: bool,
+        /// Read the value from a file (e.g. a PEM certificate or JSON key)
+        #[arg(long, value_name = "PATH")]
+        from_file: Option<String>,
         /// Optional description for the secret
         #[arg(long, short = 'd')]
         description: Option<String>,
         /// Format hint: raw, multiline, base64, json (auto-detected if omitted)
         #[arg(long, short = 'f')]
         format: Option<SecretFormat>,
+        /// Add the key to a scope in project.toml (repeatable)
+        #[arg(long = "scope")]
+        scopes: Vec<String>,
+        /// When the value stops working (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_name = "DATE", conflicts_with = "ttl")]
+        expires: Option<String>,
+        /// Expire the value this long from now (e.g. 90d, 12w, 1y)
+        #[arg(long, value_name = "AGE")]
+        ttl: Option<String>,
+    },
+    /// Generate a random value (password, token, key pair) straight into the vault
+    Generate {
+        key: String,
+        /// What to generate
+        #[arg(value_enum, default_value_t = Kind::Password)]
+        kind: Kind,
+        /// Characters (password), bytes (hex, base64, jwt-secret) or bits (rsa)
+        #[arg(long, short = 'l')]
+        length: Option<usize>,
+        /// Password character set
+        #[arg(long, value_enum)]
+        charset: Option<Charset>,
+        /// Description (defaults to how the value was generated)
+        #[arg(long, short = 'd')]
+        description: Option<String>,
+        /// Add the key to a scope in project.toml (repeatable)
+        #[arg(long = "scope")]
+        scopes: Vec<String>,
+        /// Regenerate with `cred rotate` once the value is older than this (e.g. 90d, 12w, 1y)
+        #[arg(long, value_name = "AGE")]
+        rotate_every: Option<String>,
+        /// Replace an existing secret (the old value is kept in history)
+        #[arg(long)]
+        force: bool,
     },
     /// Get a secret value
     Get { key: String },
     /// List all secrets
-    List {},
+    List {
+        /// Only list keys in this scope
+        #[arg(long)]
+        scope: Option<String>,
+    },
     /// Set or update a secret's description
     Describe {
         key: String,
         /// The description text (omit to clear)
         description: Option<String>,
     },
+    /// Set or clear when a secret's current value expires
+    Expire {
+        key: String,
+        /// Expiry date (YYYY-MM-DD or RFC 3339); omit both options to clear
+        #[arg(long, value_name = "DATE", conflicts_with = "ttl")]
+        expires: Option<String>,
+        /// Expire this long from now (e.g. 90d, 12w, 1y)
+        #[arg(long, value_name = "AGE")]
+        ttl: Option<String>,
+    },
+    /// Show certificate and private key details of a PEM secret
+    Inspect {
+        key: String,
+        /// Also check that the private key stored in this secret matches the certificate
+        #[arg(long = "key", value_name = "KEY")]
+        pair: Option<String>,
+    },
+    /// Show prior versions of a secret
+    History {
+        key: String,
+        /// Print the value stored in this version
+        #[arg(long, conflicts_with = "diff")]
+        show: Option<u32>,
+        /// Compare this version's metadata with the current value
+        #[arg(long)]
+        diff: Option<u32>,
+    },
+    /// Restore a prior version of a secret (the current value is kept in history)
+    Rollback { key: String, version: u32 },
     /// Remove from Local Vault ONLY (Use 'prune' for remote removal)
     Remove { key: String },
     /// Revoke a generated secret at the source AND locally
@@ -185,8 +509,32 @@ pub enum SecretAction {
 
 #[derive(Args, Debug)]
 pub struct ImportArgs {
-    /// Path to a .env file to import
-    pub path: String,
+    /// Path to a .env/JSON/YAML/TOML file, or an export/download file when used with --from
+    pub path: Option<String>,
+    /// File format (detected from the extension by default)
+    #[arg(long, value_enum)]
+    pub format: Option<FileFormat>,
+    /// Separator used to flatten nested JSON/YAML/TOML keys
+    #[arg(long, default_value = formats::DEFAULT_SEPARATOR)]
+    pub separator: String,
+    /// Where to import from (other sources use their CLI unless a path is given)
+    #[arg(long, value_enum, default_value_t = ImportSource::Env)]
+    pub from: ImportSource,
+    /// Password manager vault (1Password) or folder (Bitwarden) to read from
+    #[arg(long)]
+    pub vault: Option<String>,
+    /// Password manager item to import (all items in --vault if omitted)
+    #[arg(long)]
+    pub item: Option<String>,
+    /// Source environment/config to read (Doppler, Infisical); recorded as metadata
+    #[arg(long)]
+    pub environment: Option<String>,
+    /// Read the dotenv-vault DOTENV_KEY from stdin instead of the DOTENV_KEY env var
+    #[arg(long)]
+    pub dotenv_key_stdin: bool,
+    /// Expand ${VAR} references in .env values (earlier keys, then the environment)
+    #[arg(long)]
+    pub expand: bool,
     /// Overwrite existing keys instead of skipping
     #[arg(long)]
     pub overwrite: bool,
@@ -194,9 +542,18 @@ pub struct ImportArgs {
 
 #[derive(Args, Debug)]
 pub struct ExportArgs {
-    /// Path to write the exported .env file
+    /// Path to write the exported file
     pub path: String,
+    /// File format (detected from the extension by default)
+    #[arg(long, value_enum)]
+    pub format: Option<FileFormat>,
+    /// Include format, description and timestamps for lossless re-import (JSON/YAML)
+    #[arg(long)]
+    pub metadata: bool,
     /// Overwrite the output file if it exists
     #[arg(long)]
     pub force: bool,
+    /// Export only the keys in this scope (from project.toml)
+    #[arg(long)]
+    pub scope: Option<String>,
 }
@@ -0,0 +1,168 @@
+//! `cred bundle` handlers: seal selected secrets into a portable encrypted file and open it.
+
+use super::selected_keys;
+use crate::cli::{self, CliFlags};
+use crate::error::AppError;
+use crate::io::{print_json, print_out};
+use crate::{bundle, envfile, keyfile, project, team};
+use zeroize::Zeroize;
+
+/// Handle `bundle` subcommands (encrypted files for moving secrets between machines).
+pub fn handle(
+    action: cli::BundleAction,
+    flags: &CliFlags,
+    env: Option<&str>,
+) -> Result<(), AppError> {
+    match action {
+        cli::BundleAction::Create {
+            to,
+            passphrase,
+            output,
+            only,
+            scope,
+            force,
+        } => {
+            let proj = project::Project::find()?;
+            let output_path = std::path::Path::new(&output);
+            if output_path.exists() && !force {
+                return Err(AppError::user(anyhow::anyhow!(
+                    "{} exists; rerun with --force to overwrite",
+                    output
+                )));
+            }
+            let recipients = if passphrase {
+                Vec::new()
+            } else {
+                bundle::resolve_recipients(&to, &team::read_recipients(&proj.recipients_path())?)?
+            };
+            let keys = selected_keys(&proj, only, scope.as_deref())?;
+            let vault = proj.open_vault(env)?;
+            let entries = vault.select_entries(keys.as_deref())?;
+            let names: Vec<&str> = entries.iter().map(|(k, _)| k.as_str()).collect();
+            let sealed_for = if passphrase {
+                "a passphrase".to_string()
+            } else {
+                recipients
+                    .iter()
+                    .map(|r| r.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+
+            if flags.dry_run {
+                print_out(
+                    flags,
+                    &format!(
+                        "(dry-run) Would bundle {} secret(s) into {} for {}: {}",
+                        entries.len(),
+                        output,
+                        sealed_for,
+                        names.join(", ")
+                    ),
+                );
+                return Ok(());
+            }
+
+            let sealed = if passphrase {
+                let mut pass = keyfile::read_passphrase_from(
+                    bundle::BUNDLE_PASSPHRASE_ENV,
+                    "Bundle passphrase: ",
+                    true,
+                )?;
+                let sealed = bundle::Bundle::seal(
+                    &entries,
+                    bundle::Seal::Passphrase(&pass, keyfile::KdfParams::default()),
+                );
+                pass.zeroize();
+                sealed?
+            } else {
+                bundle::Bundle::seal(&entries, bundle::Seal::Recipients(&recipients))?
+            };
+            sealed.write(output_path)?;
+
+            if flags.json {
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": {
+                        "path": output,
+                        "keys": names,
+                        "passphrase": passphrase,
+                        "recipients": recipients.iter().map(|r| &r.name).collect::<Vec<_>>()
+                    }
+                });
+                print_json(&payload);
+            } else {
+                print_out(
+                    flags,
+                    &format!(
+                        "✓ Bundled {} secret(s) into {} for {}",
+                        names.len(),
+                        output,
+                        sealed_for
+                    ),
+                );
+            }
+        }
+        cli::BundleAction::Open { path, overwrite } => {
+            let proj = project::Project::find()?;
+            let sealed = bundle::Bundle::read(std::path::Path::new(&path))?;
+            let entries = if sealed.needs_passphrase() {
+                let mut pass = keyfile::read_passphrase_from(
+                    bundle::BUNDLE_PASSPHRASE_ENV,
+                    "Bundle passphrase: ",
+                    false,
+                )?;
+                let entries = sealed.open_with_passphrase(&pass);
+                pass.zeroize();
+                entries?
+            } else {
+                let identity = team::load_identity()?.ok_or_else(|| {
+                    AppError::user(anyhow::anyhow!(
+                        "No identity found; run `cred member identity` and share the public key with the sender"
+                    ))
+                })?;
+                sealed.open_with_identity(&identity)?
+            };
+
+            let mut vault = proj.open_vault(env)?;
+            let stats = envfile::import_entries(&entries, &mut vault, overwrite, flags.dry_run);
+            if !flags.dry_run {
+                vault.save()?;
+            }
+
+            if flags.json {
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": {
+                        "path": path,
+                        "created_at": sealed.created_at().to_rfc3339(),
+                        "added": stats.added,
+                        "overwritten": stats.overwritten,
+                        "skipped": stats.skipped,
+                        "dry_run": flags.dry_run
+                    }
+                });
+                print_json(&payload);
+            } else if flags.dry_run {
+                print_out(
+                    flags,
+                    &format!(
+                        "(dry-run) Would open {} (add {}, overwrite {}, skip {}).",
+                        path, stats.added, stats.overwritten, stats.skipped
+                    ),
+                );
+            } else {
+                print_out(
+                    flags,
+                    &format!(
+                        "✓ Opened {} (added {}, overwritten {}, skipped {}).",
+                        path, stats.added, stats.overwritten, stats.skipped
+                    ),
+                );
+            }
+        }
+    }
+    Ok(())
+}
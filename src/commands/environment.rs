@@ -0,0 +1,145 @@
+//! `cred project environment` handlers.
+//! Environments are declared in project.toml and each keeps its own vault file.
+
+use crate::cli::{self, CliFlags};
+use crate::error::AppError;
+use crate::fsutil;
+use crate::io::{print_json, print_out, require_yes};
+use crate::project;
+use crate::vault;
+
+/// Handle `project environment` subcommands (declared in project.toml, one vault file each).
+pub fn handle(action: cli::EnvironmentAction, flags: &CliFlags) -> Result<(), AppError> {
+    let proj = project::Project::find()?;
+    let _lock = proj.lock_config()?;
+    let mut config = proj.load_config()?;
+    match action {
+        cli::EnvironmentAction::List => {
+            let mut rows = vec![serde_json::json!({
+                "name": project::BASE_ENVIRONMENT,
+                "inherits": null,
+                "targets": {},
+            })];
+            for (name, env_cfg) in &config.environments {
+                rows.push(serde_json::json!({
+                    "name": name,
+                    "inherits": env_cfg
+                        .inherits
+                        .clone()
+                        .unwrap_or_else(|| project::BASE_ENVIRONMENT.to_string()),
+                    "targets": env_cfg.targets,
+                }));
+            }
+            if flags.json {
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": { "environments": rows }
+                });
+                print_json(&payload);
+            } else {
+                println!("Environments:");
+                println!("- {}", project::BASE_ENVIRONMENT);
+                for (name, env_cfg) in &config.environments {
+                    let parent = env_cfg
+                        .inherits
+                        .as_deref()
+                        .unwrap_or(project::BASE_ENVIRONMENT);
+                    let bindings: Vec<String> = env_cfg
+                        .targets
+                        .iter()
+                        .map(|(t, e)| format!("{} → {}", t, e))
+                        .collect();
+                    if bindings.is_empty() {
+                        println!("- {} (inherits {})", name, parent);
+                    } else {
+                        println!("- {} (inherits {}; {})", name, parent, bindings.join(", "));
+                    }
+                }
+            }
+        }
+        cli::EnvironmentAction::Add {
+            name,
+            inherits,
+            bindings,
+        } => {
+            project::validate_name("environment", &name)?;
+            if name == project::BASE_ENVIRONMENT || config.environments.contains_key(&name) {
+                return Err(AppError::user(anyhow::anyhow!(
+                    "Environment '{}' already exists",
+                    name
+                )));
+            }
+            let mut targets = std::collections::BTreeMap::new();
+            for binding in bindings {
+                let (target, remote) = binding.split_once('=').ok_or_else(|| {
+                    anyhow::anyhow!("Invalid --bind '{}': expected TARGET=ENV", binding)
+                })?;
+                targets.insert(target.trim().to_string(), remote.trim().to_string());
+            }
+            config.environments.insert(
+                name.clone(),
+                project::EnvironmentConfig {
+                    inherits: inherits.filter(|i| i != project::BASE_ENVIRONMENT),
+                    targets,
+                },
+            );
+            // Validates that the parent exists and no cycle was introduced.
+            project::resolve_environment_chain(&config, Some(&name))?;
+            if flags.dry_run {
+                print_out(
+                    flags,
+                    &format!("(dry-run) Would add environment '{}'", name),
+                );
+                return Ok(());
+            }
+            let path = proj.env_vault_path(&name);
+            if let Some(parent) = path.parent()
+                && !parent.exists()
+            {
+                fsutil::create_private_dir(parent)?;
+            }
+            let key = proj.get_master_key()?;
+            vault::Vault::load_for_project(&path, key, config.id)?.save()?;
+            proj.save_config(&config)?;
+            print_out(flags, &format!("✓ Added environment '{}'", name));
+        }
+        cli::EnvironmentAction::Remove { name } => {
+            require_yes(flags, "environment remove")?;
+            if !config.environments.contains_key(&name) {
+                return Err(AppError::user(anyhow::anyhow!(
+                    "Unknown environment '{}'",
+                    name
+                )));
+            }
+            if let Some((child, _)) = config
+                .environments
+                .iter()
+                .find(|(_, e)| e.inherits.as_deref() == Some(name.as_str()))
+            {
+                return Err(AppError::user(anyhow::anyhow!(
+                    "Environment '{}' inherits from '{}'; remove or re-parent it first",
+                    child,
+                    name
+                )));
+            }
+            if flags.dry_run {
+                print_out(
+                    flags,
+                    &format!("(dry-run) Would remove environment '{}'", name),
+                );
+                return Ok(());
+            }
+            config.environments.remove(&name);
+            proj.save_config(&config)?;
+            let path = proj.env_vault_path(&name);
+            for file in [vault::backup_path(&path), path] {
+                if file.exists() {
+                    std::fs::remove_file(&file).map_err(anyhow::Error::from)?;
+                }
+            }
+            print_out(flags, &format!("✓ Removed environment '{}'", name));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,324 @@
+//! `cred key` handlers: master key rotation, recovery copies and Shamir shares.
+
+use super::read_input;
+use crate::cli::{self, CliFlags};
+use crate::error::AppError;
+use crate::io::{print_json, print_out};
+use crate::{fsutil, keyfile, project, recovery, vault};
+use zeroize::Zeroize;
+
+/// Handle `key` subcommands (master key lifecycle).
+pub fn handle(action: cli::KeyAction, flags: &CliFlags) -> Result<(), AppError> {
+    match action {
+        cli::KeyAction::Rotate => {
+            let proj = project::Project::find()?;
+            if flags.dry_run {
+                let files = proj.vault_files(&proj.load_config()?);
+                print_out(
+                    flags,
+                    &format!(
+                        "(dry-run) Would rotate the master key and re-encrypt {} vault file(s)",
+                        files.len()
+                    ),
+                );
+                return Ok(());
+            }
+            let rotation = proj.rotate_master_key()?;
+            if flags.json {
+                let files: Vec<String> = rotation
+                    .vault_files
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect();
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": {
+                        "action": "rotate",
+                        "vault_files": files,
+                        "key_storage": rotation.key_storage,
+                    }
+                });
+                print_json(&payload);
+            } else {
+                print_out(
+                    flags,
+                    &format!(
+                        "✓ Rotated master key; re-encrypted {} vault file(s) (key stored in {})",
+                        rotation.vault_files.len(),
+                        rotation.key_storage
+                    ),
+                );
+            }
+        }
+        cli::KeyAction::Export { output, paper } => {
+            let proj = project::Project::find()?;
+            let project_id = proj
+                .load_config()?
+                .id
+                .ok_or_else(|| anyhow::anyhow!("Project ID missing in project.toml"))?;
+            if flags.dry_run {
+                print_out(
+                    flags,
+                    &format!("(dry-run) Would export a recovery key for {}", project_id),
+                );
+                return Ok(());
+            }
+            let mut key = proj.get_master_key()?;
+            let passphrase = keyfile::read_passphrase("Recovery passphrase: ", true);
+            let blob = passphrase.and_then(|mut p| {
+                let blob = recovery::RecoveryBlob::seal(
+                    project_id,
+                    &key,
+                    &p,
+                    keyfile::KdfParams::default(),
+                );
+                p.zeroize();
+                blob
+            });
+            key.zeroize();
+            let text = blob?.to_armored(paper)?;
+            match &output {
+                Some(path) => {
+                    if std::path::Path::new(path).exists() {
+                        return Err(AppError::user(anyhow::anyhow!(
+                            "Refusing to overwrite existing file: {}",
+                            path
+                        )));
+                    }
+                    fsutil::write_atomic(std::path::Path::new(path), text.as_bytes())?;
+                }
+                None if !flags.json => print!("{}", text),
+                None => {}
+            }
+            proj.record_key_backup()?;
+            if flags.json {
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": {
+                        "action": "export",
+                        "project_id": project_id,
+                        "output": output,
+                        "recovery_key": if output.is_none() { Some(&text) } else { None },
+                    }
+                });
+                print_json(&payload);
+            } else if let Some(path) = &output {
+                print_out(flags, &format!("✓ Wrote recovery key to {}", path));
+                print_out(
+                    flags,
+                    "  Store it offline; anyone with it and the passphrase can decrypt the vault.",
+                );
+            }
+        }
+        cli::KeyAction::Import { path, project_id } => {
+            let blob = recovery::RecoveryBlob::from_armored(&read_input(&path)?)?;
+            let proj = project::Project::find().ok();
+            let project_id = expected_project_id(project_id, proj.as_ref())?;
+            if blob.project_id != project_id {
+                return Err(AppError::user(anyhow::anyhow!(
+                    "Recovery key belongs to project {}, not {}",
+                    blob.project_id,
+                    project_id
+                )));
+            }
+            if flags.dry_run {
+                print_out(
+                    flags,
+                    &format!("(dry-run) Would restore the master key for {}", project_id),
+                );
+                return Ok(());
+            }
+            let mut passphrase = keyfile::read_passphrase("Recovery passphrase: ", false)?;
+            let key = blob.open(&passphrase);
+            passphrase.zeroize();
+            restore_master_key(proj.as_ref(), project_id, key?, "import", flags)?;
+        }
+        cli::KeyAction::Split {
+            shares,
+            threshold,
+            output_dir,
+            paper,
+        } => {
+            let proj = project::Project::find()?;
+            let project_id = proj
+                .load_config()?
+                .id
+                .ok_or_else(|| anyhow::anyhow!("Project ID missing in project.toml"))?;
+            if flags.dry_run {
+                print_out(
+                    flags,
+                    &format!(
+                        "(dry-run) Would split the master key into {} shares (threshold {})",
+                        shares, threshold
+                    ),
+                );
+                return Ok(());
+            }
+            let mut key = proj.get_master_key()?;
+            let split = recovery::split_key(project_id, &key, shares, threshold);
+            key.zeroize();
+            let split = split?;
+
+            let mut files = Vec::new();
+            if let Some(dir) = &output_dir {
+                let dir = std::path::Path::new(dir);
+                std::fs::create_dir_all(dir).map_err(|e| {
+                    AppError::user(anyhow::anyhow!("Failed to create {}: {}", dir.display(), e))
+                })?;
+                let paths: Vec<_> = split
+                    .iter()
+                    .map(|share| {
+                        dir.join(format!(
+                            "cred-share-{}-of-{}.txt",
+                            share.index(),
+                            share.total
+                        ))
+                    })
+                    .collect();
+                // Check every name first so an existing file doesn't leave a partial set.
+                if let Some(path) = paths.iter().find(|p| p.exists()) {
+                    return Err(AppError::user(anyhow::anyhow!(
+                        "Refusing to overwrite existing file: {}",
+                        path.display()
+                    )));
+                }
+                for (share, path) in split.iter().zip(&paths) {
+                    fsutil::write_new(path, share.to_armored(paper).as_bytes())
+                        .map_err(AppError::user)?;
+                    files.push(path.display().to_string());
+                }
+            }
+            proj.record_key_backup()?;
+            if flags.json {
+                let texts: Vec<String> = if output_dir.is_none() {
+                    split.iter().map(|s| s.to_armored(paper)).collect()
+                } else {
+                    Vec::new()
+                };
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": {
+                        "action": "split",
+                        "project_id": project_id,
+                        "shares": shares,
+                        "threshold": threshold,
+                        "files": files,
+                        "share_texts": texts,
+                    }
+                });
+                print_json(&payload);
+            } else if output_dir.is_some() {
+                for f in &files {
+                    println!("{}", f);
+                }
+                print_out(
+                    flags,
+                    &format!(
+                        "✓ Wrote {} shares; any {} recover the key. Give each to a different person.",
+                        shares, threshold
+                    ),
+                );
+            } else {
+                for share in &split {
+                    println!("{}", share.to_armored(paper));
+                }
+            }
+        }
+        cli::KeyAction::Combine { paths, project_id } => {
+            let mut shares = Vec::new();
+            for path in &paths {
+                shares.extend(recovery::KeyShare::parse_all(&read_input(path)?)?);
+            }
+            let proj = project::Project::find().ok();
+            let project_id = expected_project_id(project_id, proj.as_ref())?;
+            if let Some(share) = shares.iter().find(|s| s.project_id != project_id) {
+                return Err(AppError::user(anyhow::anyhow!(
+                    "Share {} belongs to project {}, not {}",
+                    share.index(),
+                    share.project_id,
+                    project_id
+                )));
+            }
+            let (_, key) = recovery::combine_shares(&shares)?;
+            if flags.dry_run {
+                let mut key = key;
+                key.zeroize();
+                print_out(
+                    flags,
+                    &format!("(dry-run) Would restore the master key for {}", project_id),
+                );
+                return Ok(());
+            }
+            restore_master_key(proj.as_ref(), project_id, key, "combine", flags)?;
+        }
+    }
+    Ok(())
+}
+
+/// Project id a recovered key must belong to: `--project-id`, else the current project's.
+fn expected_project_id(
+    given: Option<uuid::Uuid>,
+    proj: Option<&project::Project>,
+) -> Result<uuid::Uuid, AppError> {
+    let found = match (given, proj) {
+        (Some(id), _) => Some(id),
+        (None, Some(p)) => p.load_config()?.id,
+        (None, None) => None,
+    };
+    found.ok_or_else(|| AppError::user(anyhow::anyhow!("Not in a cred project; pass --project-id")))
+}
+
+/// Store a recovered master key where this project reads it from, after checking it opens
+/// the local vault. Key file projects are re-wrapped under a new passphrase and team
+/// projects re-sealed for their members; otherwise the key goes into the keyring.
+fn restore_master_key(
+    proj: Option<&project::Project>,
+    project_id: uuid::Uuid,
+    mut key: [u8; 32],
+    action: &str,
+    flags: &CliFlags,
+) -> Result<(), AppError> {
+    if let Some(p) = proj
+        && p.vault_path.exists()
+        && let Err(e) = vault::Vault::load_for_project(&p.vault_path, key, Some(project_id))
+    {
+        key.zeroize();
+        return Err(AppError::user(
+            e.context("Recovered key does not open this vault"),
+        ));
+    }
+    let stored = match proj {
+        Some(p) if p.key_file_path().exists() => {
+            keyfile::read_passphrase("New vault passphrase: ", true).and_then(|mut passphrase| {
+                let stored = p.restore_master_key(&key, Some(&passphrase));
+                passphrase.zeroize();
+                stored
+            })
+        }
+        Some(p) => p.restore_master_key(&key, None),
+        None => project::store_keyring_key(&project_id, &key),
+    };
+    key.zeroize();
+    stored?;
+    let key_storage = proj.map_or("keyring", |p| p.key_storage());
+    if flags.json {
+        let payload = serde_json::json!({
+            "api_version": "1",
+            "status": "ok",
+            "data": { "action": action, "project_id": project_id, "key_storage": key_storage }
+        });
+        print_json(&payload);
+    } else {
+        print_out(
+            flags,
+            &format!(
+                "✓ Restored master key for {} (key stored in {})",
+                project_id, key_storage
+            ),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+//! `cred member` handlers: share a project's vaults with teammates listed in `.cred/recipients`.
+
+use crate::cli::{self, CliFlags};
+use crate::error::AppError;
+use crate::io::{print_json, print_out, require_yes};
+use crate::{project, team};
+
+/// Handle `member` subcommands (team sharing via `.cred/recipients`).
+pub fn handle(action: cli::MemberAction, flags: &CliFlags) -> Result<(), AppError> {
+    let recipients_json = |recipients: &[team::Recipient]| -> Vec<serde_json::Value> {
+        recipients
+            .iter()
+            .map(|r| serde_json::json!({ "name": r.name, "public_key": r.public_key }))
+            .collect()
+    };
+    match action {
+        cli::MemberAction::Identity => {
+            let identity = team::load_or_create_identity()?;
+            if flags.json {
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": { "public_key": identity.public_b64() }
+                });
+                print_json(&payload);
+            } else {
+                println!("{}", identity.public_b64());
+            }
+        }
+        cli::MemberAction::List => {
+            let proj = project::Project::find()?;
+            let recipients = team::read_recipients(&proj.recipients_path())?;
+            let me = team::load_identity()?.map(|i| i.public_b64());
+            if flags.json {
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": { "members": recipients_json(&recipients) }
+                });
+                print_json(&payload);
+            } else if recipients.is_empty() {
+                println!(
+                    "Vault is not shared. Add a member with `cred member add NAME PUBLIC_KEY`."
+                );
+            } else {
+                println!("Members:");
+                for r in &recipients {
+                    let you = if me.as_deref() == Some(r.public_key.as_str()) {
+                        " (you)"
+                    } else {
+                        ""
+                    };
+                    println!("- {}{}  {}", r.name, you, r.public_key);
+                }
+            }
+        }
+        cli::MemberAction::Add { name, public_key } => {
+            let proj = project::Project::find()?;
+            if flags.dry_run {
+                print_out(flags, &format!("(dry-run) Would add member '{}'", name));
+                return Ok(());
+            }
+            let recipients = proj.add_member(&name, &public_key)?;
+            let gitignore_updated = proj.share_gitignore()?;
+            if flags.json {
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": {
+                        "action": "add",
+                        "name": name,
+                        "members": recipients_json(&recipients),
+                        "gitignore_updated": gitignore_updated
+                    }
+                });
+                print_json(&payload);
+            } else {
+                print_out(
+                    flags,
+                    &format!(
+                        "✓ Added member '{}'; project key wrapped for {} member(s)",
+                        name,
+                        recipients.len()
+                    ),
+                );
+                if gitignore_updated {
+                    print_out(
+                        flags,
+                        "✓ Updated .gitignore so only project.toml, the encrypted vaults, recipients and keys.wrapped can be committed",
+                    );
+                    print_out(
+                        flags,
+                        "  Commit them with `git add .cred`; master.key, locks and backups stay local.",
+                    );
+                }
+            }
+        }
+        cli::MemberAction::Remove { name } => {
+            require_yes(flags, "member remove")?;
+            let proj = project::Project::find()?;
+            if flags.dry_run {
+                print_out(flags, &format!("(dry-run) Would remove member '{}'", name));
+                return Ok(());
+            }
+            let recipients = proj.remove_member(&name)?;
+            if flags.json {
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": { "action": "remove", "name": name, "members": recipients_json(&recipients) }
+                });
+                print_json(&payload);
+            } else {
+                print_out(flags, &format!("✓ Removed member '{}'", name));
+                print_out(
+                    flags,
+                    "  They may still hold the current key; run `cred key rotate` to revoke it.",
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! Subcommand handlers dispatched from `run` in `main.rs`.
+//! Each module owns one command group and reports through `io` like the rest of the CLI.
+
+pub mod bundle;
+pub mod environment;
+pub mod key;
+pub mod member;
+pub mod rotate;
+pub mod secret_history;
+pub mod secret_inspect;
+
+use crate::error::AppError;
+use crate::project::{self, resolve_repo_binding};
+use crate::{config, targets};
+
+/// Read a file, or stdin when `path` is "-".
+pub fn read_input(path: &str) -> Result<String, AppError> {
+    if path == "-" {
+        std::io::read_to_string(std::io::stdin())
+            .map_err(|e| AppError::user(anyhow::anyhow!("Failed to read stdin: {}", e)))
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(|e| AppError::user(anyhow::anyhow!("Failed to read {}: {}", path, e)))
+    }
+}
+
+/// Keys chosen by `--only` or `--scope`; `None` means every key.
+pub fn selected_keys(
+    proj: &project::Project,
+    only: Vec<String>,
+    scope: Option<&str>,
+) -> Result<Option<Vec<String>>, AppError> {
+    Ok(match scope {
+        Some(s) => Some(project::scope_keys(&proj.load_config()?, s)?),
+        None if !only.is_empty() => Some(only),
+        None => None,
+    })
+}
+
+/// Token and push options (repository, remote environment) for pushing `env` to `target`.
+pub fn push_options(
+    target: targets::Target,
+    proj_config: Option<&project::ProjectConfig>,
+    env: Option<&str>,
+    repo: Option<String>,
+) -> Result<(String, targets::PushOptions), AppError> {
+    let token = config::get_target_token(&target.to_string())?
+        .ok_or_else(|| anyhow::anyhow!("No token found for {}.", target))?;
+
+    let git_info = project::detect_git(None);
+    let bound_repo = proj_config.and_then(|c| c.git_repo.clone());
+    let environment =
+        proj_config.and_then(|c| project::target_environment(c, env, &target.to_string()));
+
+    let repo = resolve_repo_binding(git_info.and_then(|g| g.repo_slug), bound_repo, repo, "push")
+        .map_err(AppError::from)?;
+
+    if matches!(target, targets::Target::Github) && repo.is_none() {
+        return Err(AppError::git(anyhow::anyhow!(
+            "GitHub push requires a repository. Provide --repo owner/name or initialize inside a git repo so it can be recorded."
+        )));
+    }
+
+    Ok((token, targets::PushOptions { repo, environment }))
+}
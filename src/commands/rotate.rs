@@ -0,0 +1,137 @@
+//! `cred rotate` handler: regenerate secrets from their rotation policies, then optionally push.
+
+use super::push_options;
+use crate::cli::{self, CliFlags};
+use crate::error::{AppError, ExitCode};
+use crate::io::{print_json, print_out};
+use crate::targets::{self, TargetAdapter};
+use crate::{project, rotation};
+use zeroize::Zeroize;
+
+/// Regenerate secrets from their rotation policies, then optionally push them.
+pub async fn handle(
+    args: cli::RotateArgs,
+    flags: &CliFlags,
+    env: Option<&str>,
+) -> Result<(), AppError> {
+    let proj = project::Project::find()?;
+    let mut vault = proj.open_vault(env)?;
+    let now = chrono::Utc::now();
+    let keys = if args.due {
+        rotation::due_keys(&vault, now)
+    } else {
+        args.keys.clone()
+    };
+
+    if keys.is_empty() {
+        if flags.json {
+            let payload = serde_json::json!({
+                "api_version": "1",
+                "status": "ok",
+                "data": { "rotated": [], "pushed": [] }
+            });
+            print_json(&payload);
+        } else {
+            print_out(flags, "No secrets are due for rotation.");
+        }
+        return Ok(());
+    }
+
+    // Resolve every push target before touching the vault, so a missing token fails early.
+    let proj_config = proj.load_config().ok();
+    let mut pushes = Vec::new();
+    for target in &args.push {
+        let target_impl = targets::get(*target)
+            .ok_or_else(|| AppError::user(anyhow::anyhow!("Target '{}' not supported.", target)))?;
+        let (token, options) = push_options(*target, proj_config.as_ref(), env, args.repo.clone())?;
+        pushes.push((*target, target_impl, token, options));
+    }
+
+    if flags.dry_run {
+        for key in &keys {
+            let entry = vault
+                .get_entry(key)
+                .ok_or_else(|| AppError::user(anyhow::anyhow!("Secret '{}' not found", key)))?;
+            let policy = entry.rotation.as_ref().ok_or_else(|| {
+                AppError::user(anyhow::anyhow!("'{}' has no rotation policy", key))
+            })?;
+            print_out(
+                flags,
+                &format!(
+                    "(dry-run) Would rotate {} ({}, due {})",
+                    key,
+                    policy.recipe.describe(),
+                    policy.due_at(entry.updated_at).format("%Y-%m-%d")
+                ),
+            );
+        }
+        for (target, ..) in &pushes {
+            print_out(flags, &format!("(dry-run) Would push them to {}", target));
+        }
+        return Ok(());
+    }
+
+    let mut rotated = Vec::new();
+    for key in &keys {
+        rotated.push(rotation::rotate(&mut vault, key)?);
+    }
+    vault.save()?;
+
+    let mut pushed = Vec::new();
+    if !pushes.is_empty() {
+        let mut values = std::collections::HashMap::new();
+        for r in &rotated {
+            for k in std::iter::once(&r.key).chain(r.public_key.as_ref()) {
+                if let Some(value) = vault.get(k) {
+                    values.insert(k.clone(), value.clone());
+                }
+            }
+        }
+        for (target, target_impl, token, options) in &pushes {
+            target_impl.push(&values, token, options).await.map_err(|e| {
+                AppError::new(
+                    ExitCode::NetworkError,
+                    anyhow::anyhow!(
+                        "Rotated {} secret(s) locally but failed to push to {}: {}. Rerun `cred push {}`.",
+                        rotated.len(),
+                        target,
+                        e,
+                        target
+                    ),
+                )
+            })?;
+            pushed.push(target.to_string());
+        }
+        values.values_mut().for_each(Zeroize::zeroize);
+    }
+
+    if flags.json {
+        let payload = serde_json::json!({
+            "api_version": "1",
+            "status": "ok",
+            "data": {
+                "rotated": rotated
+                    .iter()
+                    .map(|r| serde_json::json!({
+                        "key": r.key,
+                        "version": r.version,
+                        "public_key": r.public_key,
+                    }))
+                    .collect::<Vec<_>>(),
+                "pushed": pushed,
+            }
+        });
+        print_json(&payload);
+    } else {
+        for r in &rotated {
+            print_out(flags, &format!("✓ Rotated {} (now v{})", r.key, r.version));
+            if let Some(public_key) = &r.public_key {
+                print_out(flags, &format!("✓ Updated public key {}", public_key));
+            }
+        }
+        for target in &pushed {
+            print_out(flags, &format!("✓ Pushed rotated secrets to {}", target));
+        }
+    }
+    Ok(())
+}
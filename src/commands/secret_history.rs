@@ -0,0 +1,151 @@
+//! `cred secret history` handler: list, show or diff a secret's stored versions.
+
+use crate::cli::CliFlags;
+use crate::error::AppError;
+use crate::io::print_json;
+use crate::vault;
+
+/// List, show or diff the stored history of a single secret.
+pub fn handle(
+    vault: &vault::Vault,
+    key: &str,
+    show: Option<u32>,
+    diff: Option<u32>,
+    flags: &CliFlags,
+) -> Result<(), AppError> {
+    let entry = vault
+        .get_entry(key)
+        .ok_or_else(|| AppError::user(anyhow::anyhow!("Secret '{}' not found", key)))?;
+    let lookup = |version: u32| {
+        entry.history_version(version).ok_or_else(|| {
+            AppError::user(anyhow::anyhow!(
+                "Version {} of '{}' not found in history",
+                version,
+                key
+            ))
+        })
+    };
+
+    if let Some(version) = show {
+        let v = lookup(version)?;
+        if flags.json {
+            let payload = serde_json::json!({
+                "api_version": "1",
+                "status": "ok",
+                "data": {
+                    "key": key,
+                    "version": v.version,
+                    "value": v.value,
+                    "format": v.format.to_string(),
+                    "updated_at": v.updated_at.to_rfc3339(),
+                    "description": v.description,
+                }
+            });
+            print_json(&payload);
+        } else {
+            println!("{}", v.value);
+        }
+        return Ok(());
+    }
+
+    if let Some(version) = diff {
+        let v = lookup(version)?;
+        let current_hash = entry.hash.clone().unwrap_or_default();
+        let mut changes = serde_json::Map::new();
+        let mut record = |field: &str, from: serde_json::Value, to: serde_json::Value| {
+            if from != to {
+                changes.insert(
+                    field.to_string(),
+                    serde_json::json!({ "from": from, "to": to }),
+                );
+            }
+        };
+        record(
+            "format",
+            v.format.to_string().into(),
+            entry.format.to_string().into(),
+        );
+        record(
+            "description",
+            v.description.clone().into(),
+            entry.description.clone().into(),
+        );
+        record("hash", v.hash.clone().into(), current_hash.into());
+        record("length", v.value.len().into(), entry.value.len().into());
+        if flags.json {
+            let payload = serde_json::json!({
+                "api_version": "1",
+                "status": "ok",
+                "data": {
+                    "key": key,
+                    "from": v.version,
+                    "to": entry.version,
+                    "changes": changes,
+                }
+            });
+            print_json(&payload);
+        } else if changes.is_empty() {
+            println!(
+                "{}: version {} matches current (version {})",
+                key, v.version, entry.version
+            );
+        } else {
+            println!("{}: version {} → {}", key, v.version, entry.version);
+            for (field, change) in &changes {
+                println!("  {}: {} → {}", field, change["from"], change["to"]);
+            }
+        }
+        return Ok(());
+    }
+
+    let short = |hash: &str| hash.chars().take(12).collect::<String>();
+    if flags.json {
+        let mut versions: Vec<serde_json::Value> = entry
+            .history
+            .iter()
+            .map(|v| {
+                serde_json::json!({
+                    "version": v.version,
+                    "format": v.format.to_string(),
+                    "hash": v.hash,
+                    "updated_at": v.updated_at.to_rfc3339(),
+                    "description": v.description,
+                    "current": false,
+                })
+            })
+            .collect();
+        versions.push(serde_json::json!({
+            "version": entry.version,
+            "format": entry.format.to_string(),
+            "hash": entry.hash,
+            "updated_at": entry.updated_at.to_rfc3339(),
+            "description": entry.description,
+            "current": true,
+        }));
+        let payload = serde_json::json!({
+            "api_version": "1",
+            "status": "ok",
+            "data": { "key": key, "versions": versions }
+        });
+        print_json(&payload);
+    } else {
+        println!("History for {}:", key);
+        for v in &entry.history {
+            println!(
+                "  v{}  {}  {}  {}",
+                v.version,
+                v.updated_at.format("%Y-%m-%d %H:%M:%S"),
+                v.format,
+                short(&v.hash)
+            );
+        }
+        println!(
+            "  v{}  {}  {}  {}  (current)",
+            entry.version,
+            entry.updated_at.format("%Y-%m-%d %H:%M:%S"),
+            entry.format,
+            short(entry.hash.as_deref().unwrap_or("-"))
+        );
+    }
+    Ok(())
+}
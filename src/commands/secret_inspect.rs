@@ -0,0 +1,144 @@
+//! `cred secret inspect` handler: certificate and private key details of a PEM secret.
+
+use crate::cli::CliFlags;
+use crate::error::AppError;
+use crate::io::{print_err, print_json, print_out};
+use crate::{cert, expiry, vault};
+
+/// Show certificate and key details of a PEM secret, and check that its certificate
+/// matches its own private key or the one stored in `pair`.
+pub fn handle(
+    vault: &vault::Vault,
+    key: &str,
+    pair: Option<&str>,
+    flags: &CliFlags,
+) -> Result<(), AppError> {
+    let load = |key: &str| -> Result<cert::PemInfo, AppError> {
+        let entry = vault
+            .get_entry(key)
+            .ok_or_else(|| AppError::user(anyhow::anyhow!("Secret '{}' not found", key)))?;
+        let info = cert::inspect(&entry.value)
+            .map_err(|e| AppError::user(e.context(format!("Failed to parse '{}'", key))))?;
+        if info.is_empty() {
+            return Err(AppError::user(anyhow::anyhow!(
+                "'{}' holds no certificate or private key",
+                key
+            )));
+        }
+        Ok(info)
+    };
+    let info = load(key)?;
+    let paired = pair.map(load).transpose()?;
+
+    // Every private key in either secret is checked against the leaf certificate.
+    let keys: Vec<&cert::PrivateKey> = info
+        .private_keys
+        .iter()
+        .chain(paired.iter().flat_map(|p| p.private_keys.iter()))
+        .collect();
+    let matches: Option<Vec<Option<bool>>> = info
+        .certificates
+        .first()
+        .filter(|_| !keys.is_empty())
+        .map(|leaf| keys.iter().map(|k| cert::key_matches(leaf, k)).collect());
+    if pair.is_some() && matches.is_none() {
+        return Err(AppError::user(anyhow::anyhow!(
+            "--key needs a certificate in '{}' and a private key in '{}'",
+            key,
+            pair.unwrap_or_default()
+        )));
+    }
+
+    let now = chrono::Utc::now();
+    if flags.json {
+        let payload = serde_json::json!({
+            "api_version": "1",
+            "status": "ok",
+            "data": {
+                "key": key,
+                "certificates": info.certificates,
+                "private_keys": info.private_keys,
+                "paired_key": pair,
+                "paired_private_keys": paired.as_ref().map(|p| &p.private_keys),
+                "key_matches": matches,
+            }
+        });
+        print_json(&payload);
+    } else {
+        for (n, c) in info.certificates.iter().enumerate() {
+            let role = if n == 0 {
+                "Certificate"
+            } else {
+                "Chain certificate"
+            };
+            let status = match expiry::status(Some(c.not_after), now) {
+                Some(_) => format!(" ({})", expiry::describe(c.not_after, now)),
+                None => String::new(),
+            };
+            print_out(flags, &format!("{}:", role));
+            print_out(flags, &format!("  Subject:  {}", c.subject));
+            print_out(flags, &format!("  Issuer:   {}", c.issuer));
+            print_out(flags, &format!("  Serial:   {}", c.serial));
+            if !c.sans.is_empty() {
+                print_out(flags, &format!("  SANs:     {}", c.sans.join(", ")));
+            }
+            print_out(
+                flags,
+                &format!(
+                    "  Valid:    {} to {}{}",
+                    c.not_before.format("%Y-%m-%d"),
+                    c.not_after.format("%Y-%m-%d"),
+                    status
+                ),
+            );
+            print_out(
+                flags,
+                &format!("  Key:      {}", describe_key(&c.public_key)),
+            );
+            print_out(flags, &format!("  SHA-256:  {}", c.fingerprint_sha256));
+        }
+        for k in &keys {
+            let kind = k
+                .key
+                .as_ref()
+                .map(describe_key)
+                .unwrap_or_else(|| "unknown type".to_string());
+            let encrypted = if k.encrypted { ", encrypted" } else { "" };
+            print_out(
+                flags,
+                &format!("Private key: {} ({}{})", kind, k.encoding, encrypted),
+            );
+        }
+        for m in matches.iter().flatten() {
+            match m {
+                Some(true) => print_out(flags, "✓ Private key matches the certificate"),
+                Some(false) => print_err(flags, "x Private key does not match the certificate"),
+                None => print_out(
+                    flags,
+                    "Cannot check an encrypted private key against the certificate",
+                ),
+            }
+        }
+    }
+
+    if matches.iter().flatten().any(|m| *m == Some(false)) {
+        return Err(AppError::user(anyhow::anyhow!(
+            "Certificate in '{}' and private key do not belong together",
+            key
+        )));
+    }
+    Ok(())
+}
+
+/// `EC P-256 (256 bits)`, `RSA (2048 bits)`, `Ed25519 (256 bits)`.
+fn describe_key(key: &cert::KeyInfo) -> String {
+    let mut s = key.kind.clone();
+    if let Some(curve) = &key.curve {
+        s.push(' ');
+        s.push_str(curve);
+    }
+    if let Some(bits) = key.bits {
+        s.push_str(&format!(" ({} bits)", bits));
+    }
+    s
+}
@@ -17,6 +17,8 @@ use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 use toml::Value;
 
+use crate::fsutil;
+
 /// Versioning information for the global config.
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CredMeta {
@@ -80,7 +82,7 @@ pub fn ensure_config_at(config_dir: &Path) -> Result<PathBuf> {
     if !file_path.exists() {
         let default_config = default_config();
         let content = toml::to_string_pretty(&default_config)?;
-        fs::write(&file_path, content)?;
+        fsutil::write_atomic(&file_path, content.as_bytes())?;
     }
     Ok(file_path)
 }
@@ -113,8 +115,12 @@ fn load_raw() -> Result<Value> {
 fn save_raw(val: &Value) -> Result<()> {
     let config_path = ensure_global_config_exists()?;
     let toml_string = toml::to_string_pretty(val)?;
-    fs::write(&config_path, toml_string)?;
-    Ok(())
+    fsutil::write_atomic(&config_path, toml_string.as_bytes())
+}
+
+/// Hold the global config lock for a load-modify-save cycle.
+fn lock_config() -> Result<fsutil::FileLock> {
+    fsutil::lock(&ensure_global_config_exists()?)
 }
 
 mod toml_path {
@@ -178,10 +184,10 @@ mod toml_path {
                 return;
             }
         }
-        if let Some(last) = path.last() {
-            if let Some(tbl) = current.as_table_mut() {
-                tbl.remove(*last);
-            }
+        if let Some(last) = path.last()
+            && let Some(tbl) = current.as_table_mut()
+        {
+            tbl.remove(*last);
         }
     }
 
@@ -202,6 +208,7 @@ mod toml_path {
 
 /// Set a config value at a dotted path, coercing primitive types.
 pub fn config_set(key_path: &str, val: &str) -> Result<()> {
+    let _lock = lock_config()?;
     let mut root = load_raw()?;
     let parts: Vec<&str> = key_path.split('.').filter(|s| !s.is_empty()).collect();
     if parts.is_empty() {
@@ -224,6 +231,7 @@ pub fn config_get(key_path: &str) -> Result<Option<Value>> {
 
 /// Remove a config value at a dotted path.
 pub fn config_unset(key_path: &str) -> Result<()> {
+    let _lock = lock_config()?;
     let mut root = load_raw()?;
     let parts: Vec<&str> = key_path.split('.').filter(|s| !s.is_empty()).collect();
     if parts.is_empty() {
@@ -267,6 +275,7 @@ fn default_config() -> GlobalConfig {
 
 /// Persist a target token reference in config and store the token via keystore backend.
 pub fn set_target_token(target: &str, token: &str) -> Result<()> {
+    let _lock = lock_config()?;
     let mut config = load()?;
     let auth_ref = format!("cred:target:{}:default", target);
     config
@@ -277,7 +286,7 @@ pub fn set_target_token(target: &str, token: &str) -> Result<()> {
 
     let config_path = ensure_global_config_exists()?;
     let toml_string = toml::to_string_pretty(&config)?;
-    fs::write(&config_path, toml_string)?;
+    fsutil::write_atomic(&config_path, toml_string.as_bytes())?;
 
     keystore::set(&auth_ref, token)?;
     Ok(())
@@ -295,6 +304,7 @@ pub fn get_target_token(target: &str) -> Result<Option<String>> {
 
 /// Remove a target token reference and delete the stored secret if present.
 pub fn remove_target_token(target: &str) -> Result<()> {
+    let _lock = lock_config()?;
     let mut config = load()?;
     if let Some(tcfg) = config.targets.remove(target) {
         if let Some(auth_ref) = tcfg.auth_ref {
@@ -302,7 +312,7 @@ pub fn remove_target_token(target: &str) -> Result<()> {
         }
         let config_path = ensure_global_config_exists()?;
         let toml_string = toml::to_string_pretty(&config)?;
-        fs::write(&config_path, toml_string)?;
+        fsutil::write_atomic(&config_path, toml_string.as_bytes())?;
         println!("✓ Removed authentication for '{}'", target);
     } else {
         println!("Target '{}' was not configured.", target);
@@ -310,6 +320,29 @@ pub fn remove_target_token(target: &str) -> Result<()> {
     Ok(())
 }
 
+/// Existing global files that only their owner should be able to read.
+pub fn sensitive_paths() -> Vec<PathBuf> {
+    let mut paths = vec![keystore::file_path()];
+    if let Ok(dir) = resolve_config_dir() {
+        paths.insert(0, dir.join("global.toml"));
+    }
+    paths.retain(|p| p.exists());
+    paths
+}
+
+/// Keystore reference for this user's team identity (X25519 private key).
+const IDENTITY_AUTH_REF: &str = "cred:identity:default";
+
+/// Store this user's team identity key (base64) via the keystore backend.
+pub fn set_identity_key(secret_b64: &str) -> Result<()> {
+    keystore::set(IDENTITY_AUTH_REF, secret_b64)
+}
+
+/// Retrieve this user's team identity key (base64), if one has been created.
+pub fn get_identity_key() -> Result<Option<String>> {
+    keystore::get(IDENTITY_AUTH_REF)
+}
+
 // ---------- Keystore backends ----------
 
 mod keystore {
@@ -327,13 +360,7 @@ mod keystore {
         match std::env::var("CRED_KEYSTORE").as_deref() {
             Ok("memory") => KeystoreBackend::Memory,
             Ok("file") => {
-                let path = std::env::var("CRED_KEYSTORE_FILE")
-                    .map(PathBuf::from)
-                    .unwrap_or_else(|_| {
-                        resolve_config_dir()
-                            .unwrap_or_else(|_| PathBuf::from("."))
-                            .join("keystore.enc")
-                    });
+                let path = file_path();
                 let key_b64 = std::env::var("CRED_KEYSTORE_FILE_KEY")
                     .expect("CRED_KEYSTORE_FILE_KEY (base64 32 bytes) required for file keystore");
                 let key_raw = BASE64
@@ -351,6 +378,17 @@ mod keystore {
         }
     }
 
+    /// Location of the file backend's `keystore.enc`.
+    pub fn file_path() -> PathBuf {
+        std::env::var("CRED_KEYSTORE_FILE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                resolve_config_dir()
+                    .unwrap_or_else(|_| PathBuf::from("."))
+                    .join("keystore.enc")
+            })
+    }
+
     static MEMORY_KEYSTORE: OnceLock<std::sync::Mutex<HashMap<String, String>>> = OnceLock::new();
 
     /// Store a token in the active keystore backend.
@@ -443,6 +481,10 @@ mod keystore {
 
     /// Write/update a token in the file-based keystore.
     fn keystore_file_write(path: &Path, key: &[u8; 32], auth_ref: &str, token: &str) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _lock = fsutil::lock(path)?;
         let mut map = if path.exists() {
             keystore_file_load_all(path, key)?
         } else {
@@ -457,6 +499,7 @@ mod keystore {
         if !path.exists() {
             return Ok(());
         }
+        let _lock = fsutil::lock(path)?;
         let mut map = keystore_file_load_all(path, key)?;
         map.remove(auth_ref);
         keystore_file_save_all(path, key, &map)
@@ -502,8 +545,7 @@ mod keystore {
             ciphertext: BASE64.encode(ciphertext),
         };
         let data = serde_json::to_vec_pretty(&enc)?;
-        fs::write(path, data)?;
-        Ok(())
+        fsutil::write_atomic(path, &data)
     }
 }
 
@@ -516,7 +558,7 @@ mod tests {
         assert_eq!(toml_path::parse_value("true"), Value::Boolean(true));
         assert_eq!(toml_path::parse_value("false"), Value::Boolean(false));
         assert_eq!(toml_path::parse_value("42"), Value::Integer(42));
-        assert_eq!(toml_path::parse_value("3.14"), Value::Float(3.14));
+        assert_eq!(toml_path::parse_value("2.5"), Value::Float(2.5));
         assert_eq!(
             toml_path::parse_value("text"),
             Value::String("text".to_string())
@@ -1,9 +1,10 @@
 //! Helpers for importing/exporting .env-style files to and from the vault.
 use crate::error::AppError;
-use crate::vault::Vault;
-use anyhow::{Context, anyhow};
+use crate::vault::{SecretFormat, Vault};
+use anyhow::{Context, anyhow, bail};
+use chrono::{DateTime, Utc};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct ImportStats {
@@ -12,56 +13,293 @@ pub struct ImportStats {
     pub overwritten: usize,
 }
 
-/// Parse a .env-style file into key/value pairs. Supports `KEY=VALUE`, skips
-/// blank lines and lines starting with `#`. Errors on malformed rows.
-pub fn parse_env_file(path: &Path) -> Result<Vec<(String, String)>, AppError> {
+/// A secret read from an import source, with optional metadata to carry into the vault.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportedEntry {
+    pub key: String,
+    pub value: String,
+    pub description: Option<String>,
+    pub environment: Option<String>,
+    /// Format to record instead of detecting one from the value.
+    pub format: Option<SecretFormat>,
+    /// Original timestamps, kept when re-importing a metadata export.
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// When the value expires, if the source records it.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<(String, String)> for ImportedEntry {
+    fn from((key, value): (String, String)) -> Self {
+        ImportedEntry {
+            key,
+            value,
+            ..Default::default()
+        }
+    }
+}
+
+/// Parse a .env-style file into key/value pairs (see `parse_env_str` for the dialect).
+pub fn parse_env_file(path: &Path, expand: bool) -> Result<Vec<(String, String)>, AppError> {
     let content =
         fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_env_str(&content, expand)
+}
+
+/// Parse .env-style content already held in memory.
+///
+/// Follows the common dotenv dialect:
+/// - blank lines and `#` comments are skipped; an optional `export ` prefix is ignored
+/// - unquoted values are trimmed and end at an inline comment (` # ...`)
+/// - `'single'` and `` `backtick` `` quoted values are literal
+/// - `"double"` quoted values understand `\n`, `\r`, `\t`, `\"`, `\\` and `\$`
+/// - any quoted value may span several lines (PEM blocks)
+///
+/// With `expand`, `${VAR}` (and `${VAR:-default}`) in unquoted and double-quoted values is
+/// replaced by an earlier key from the same content, else the process environment, else
+/// the default or an empty string.
+pub fn parse_env_str(content: &str, expand: bool) -> Result<Vec<(String, String)>, AppError> {
+    let content = content.replace("\r\n", "\n");
+    let mut parser = Parser {
+        chars: content.chars().peekable(),
+        line: 1,
+        expand,
+        entries: Vec::new(),
+    };
+    parser.parse().map_err(AppError::user)?;
+    Ok(parser.entries)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    expand: bool,
+    entries: Vec<(String, String)>,
+}
 
-    let mut entries = Vec::new();
-    for (idx, raw_line) in content.lines().enumerate() {
-        let line = raw_line.trim();
-        if line.is_empty() || line.starts_with('#') {
-            continue;
+impl Parser<'_> {
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
         }
+        c
+    }
 
-        let (key_part, value_part) = line
-            .split_once('=')
-            .ok_or_else(|| anyhow!("Invalid line {}: expected KEY=VALUE", idx + 1))?;
+    fn skip_blanks(&mut self) {
+        while self.chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+    }
 
-        let key = key_part.trim();
+    /// Consume the rest of the current line, including the newline.
+    fn rest_of_line(&mut self) -> String {
+        let mut rest = String::new();
+        while let Some(c) = self.next() {
+            if c == '\n' {
+                break;
+            }
+            rest.push(c);
+        }
+        rest
+    }
+
+    fn parse(&mut self) -> anyhow::Result<()> {
+        loop {
+            self.skip_blanks();
+            match self.chars.peek() {
+                None => return Ok(()),
+                Some('\n') | Some('#') => {
+                    self.rest_of_line();
+                }
+                Some(_) => self.parse_assignment()?,
+            }
+        }
+    }
+
+    fn parse_assignment(&mut self) -> anyhow::Result<()> {
+        let line = self.line;
+        let mut key = String::new();
+        while let Some(c) = self.chars.next_if(|c| *c != '=' && *c != '\n') {
+            key.push(c);
+        }
+        if self.chars.next_if_eq(&'=').is_none() {
+            bail!("Invalid line {}: expected KEY=VALUE", line);
+        }
+        let key = key.trim();
+        let key = match key.strip_prefix("export") {
+            Some(rest) if rest.starts_with([' ', '\t']) => rest.trim_start(),
+            _ => key,
+        };
         if key.is_empty() {
-            return Err(AppError::user(anyhow!(
-                "Invalid line {}: key cannot be empty",
-                idx + 1
-            )));
+            bail!("Invalid line {}: key cannot be empty", line);
+        }
+        if key.contains(char::is_whitespace) {
+            bail!("Invalid line {}: key '{}' contains whitespace", line, key);
         }
 
-        // Preserve value as-is after the first '=' to avoid altering user content.
-        let value = value_part.to_string();
-        entries.push((key.to_string(), value));
+        self.skip_blanks();
+        let value = match self.chars.peek() {
+            Some(&quote @ ('\'' | '"' | '`')) => {
+                self.next();
+                let value = self.parse_quoted(quote, line)?;
+                let trailing = self.rest_of_line();
+                let trailing = trailing.trim();
+                if !trailing.is_empty() && !trailing.starts_with('#') {
+                    bail!(
+                        "Invalid line {}: unexpected '{}' after closing quote",
+                        line,
+                        trailing
+                    );
+                }
+                value
+            }
+            _ => {
+                let raw = self.rest_of_line();
+                // `#` starts a comment at the beginning of the value or after whitespace.
+                let comment = raw
+                    .char_indices()
+                    .find(|&(i, c)| c == '#' && (i == 0 || raw[..i].ends_with([' ', '\t'])));
+                let value = &raw[..comment.map_or(raw.len(), |(i, _)| i)];
+                let value = value.trim();
+                if self.expand {
+                    self.expand_vars(value, line)?
+                } else {
+                    value.to_string()
+                }
+            }
+        };
+        self.entries.push((key.to_string(), value));
+        Ok(())
     }
 
-    Ok(entries)
+    fn parse_quoted(&mut self, quote: char, line: usize) -> anyhow::Result<String> {
+        let mut value = String::new();
+        loop {
+            let Some(c) = self.next() else {
+                bail!("Invalid line {}: unterminated {} quote", line, quote);
+            };
+            match c {
+                c if c == quote => return Ok(value),
+                '\\' if quote == '"' => match self.next() {
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some(c @ ('"' | '\\' | '$')) => value.push(c),
+                    Some(c) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => bail!("Invalid line {}: unterminated \" quote", line),
+                },
+                '$' if quote == '"' && self.expand && self.chars.peek() == Some(&'{') => {
+                    self.next();
+                    let mut reference = String::new();
+                    loop {
+                        match self.next() {
+                            Some('}') => break,
+                            Some('\n') | None => {
+                                bail!("Invalid line {}: unterminated ${{...}}", line)
+                            }
+                            Some(c) => reference.push(c),
+                        }
+                    }
+                    value.push_str(&self.lookup(&reference, line)?);
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    /// Expand `${...}` references in an unquoted value.
+    fn expand_vars(&self, value: &str, line: usize) -> anyhow::Result<String> {
+        let mut out = String::new();
+        let mut rest = value;
+        while let Some(start) = rest.find("${") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after
+                .find('}')
+                .ok_or_else(|| anyhow!("Invalid line {}: unterminated ${{...}}", line))?;
+            out.push_str(&self.lookup(&after[..end], line)?);
+            rest = &after[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// Resolve `NAME` or `NAME:-default`: earlier keys win over the process environment.
+    fn lookup(&self, reference: &str, line: usize) -> anyhow::Result<String> {
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+        if name.is_empty() {
+            bail!("Invalid line {}: empty ${{}} reference", line);
+        }
+        let found = self
+            .entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.clone())
+            .or_else(|| std::env::var(name).ok())
+            .filter(|v| !v.is_empty());
+        Ok(found.or(default.map(str::to_string)).unwrap_or_default())
+    }
 }
 
-/// Merge parsed .env entries into the vault. By default keeps existing keys;
-/// set `overwrite` to replace existing values. Honors `dry_run` by not mutating
-/// the vault while still returning the counters that would apply.
-pub fn import_entries(
-    entries: &[(String, String)],
+/// Format `value` for the right-hand side of a .env line so `parse_env_str` reads it
+/// back unchanged (without `expand`, and with it). Plain tokens stay bare; anything
+/// else is double-quoted with newlines, quotes, backslashes and `$` escaped.
+pub fn format_env_value(value: &str) -> String {
+    let bare = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.,:/@+=%^~".contains(c));
+    if bare {
+        return value.to_string();
+    }
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '"' | '\\' | '$' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Merge parsed entries into the vault. By default keeps existing keys;
+/// set `overwrite` to replace existing values. Descriptions carried by an
+/// `ImportedEntry` are applied; existing descriptions are kept otherwise.
+/// Honors `dry_run` by not mutating the vault while still returning the
+/// counters that would apply.
+pub fn import_entries<E>(
+    entries: &[E],
     vault: &mut Vault,
     overwrite: bool,
     dry_run: bool,
-) -> ImportStats {
+) -> ImportStats
+where
+    E: Clone + Into<ImportedEntry>,
+{
     let mut stats = ImportStats::default();
 
-    for (key, value) in entries {
-        if vault.get(key).is_some() {
+    for entry in entries.iter().cloned().map(Into::into) {
+        // Only the vault's own entries count: importing into an environment
+        // overrides inherited keys rather than skipping them.
+        if vault.list_entries().contains_key(&entry.key) {
             if overwrite {
                 stats.overwritten += 1;
                 if !dry_run {
-                    vault.set(key, value);
+                    apply_entry(vault, &entry);
                 }
             } else {
                 stats.skipped += 1;
@@ -69,7 +307,7 @@ pub fn import_entries(
         } else {
             stats.added += 1;
             if !dry_run {
-                vault.set(key, value);
+                apply_entry(vault, &entry);
             }
         }
     }
@@ -77,61 +315,33 @@ pub fn import_entries(
     stats
 }
 
-/// Export vault contents to a .env-style file. Keys are sorted for stability.
-/// Refuses to overwrite unless `force` is true. If `dry_run`, no file is
-/// created but overwrite checks still apply.
-pub fn export_env_file(
-    vault: &Vault,
-    output_path: &Path,
-    force: bool,
-    dry_run: bool,
-) -> Result<usize, AppError> {
-    if output_path.exists() && !force {
-        return Err(AppError::user(anyhow!(
-            "{} exists; rerun with --force to overwrite",
-            output_path.display()
-        )));
+fn apply_entry(vault: &mut Vault, entry: &ImportedEntry) {
+    vault.set(&entry.key, &entry.value);
+    if entry.description.is_some() {
+        vault.set_description(&entry.key, entry.description.clone());
     }
+    if entry.environment.is_some() {
+        vault.set_environment(&entry.key, entry.environment.clone());
+    }
+    if let Some(format) = &entry.format {
+        vault.set_format(&entry.key, format.clone());
+    }
+    if entry.expires_at.is_some() {
+        vault.set_expiry(&entry.key, entry.expires_at);
+    }
+    if entry.created_at.is_some() || entry.updated_at.is_some() {
+        vault.set_timestamps(&entry.key, entry.created_at, entry.updated_at);
+    }
+}
 
-    let secrets = vault.list();
-    let mut entries: Vec<_> = secrets.iter().collect();
-    entries.sort_by(|a, b| a.0.cmp(b.0));
-
+/// Render `KEY=value` lines in the given order, quoting values with `format_env_value`.
+pub fn render_env<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
     let mut body = String::new();
-    for (key, value) in entries {
+    for (key, value) in pairs {
         body.push_str(key);
         body.push('=');
-        body.push_str(value);
+        body.push_str(&format_env_value(value));
         body.push('\n');
     }
-    let line_count = body.lines().count();
-
-    if dry_run {
-        return Ok(line_count);
-    }
-
-    if let Some(parent) = output_path.parent() {
-        if !parent.as_os_str().is_empty() && !parent.exists() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create {}", parent.display()))?;
-        }
-    }
-
-    let tmp_path = tmp_path(output_path);
-    fs::write(&tmp_path, &body)
-        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
-    fs::rename(&tmp_path, output_path)
-        .with_context(|| format!("Failed to persist to {}", output_path.display()))?;
-
-    Ok(line_count)
-}
-
-fn tmp_path(path: &Path) -> PathBuf {
-    let mut tmp = path.to_path_buf();
-    let file_name = path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "env".to_string());
-    tmp.set_file_name(format!("{}.tmp", file_name));
-    tmp
+    body
 }
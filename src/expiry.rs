@@ -0,0 +1,149 @@
+//! Expiry dates for secrets (`--expires 2026-12-31` / `--ttl 90d`).
+//!
+//! Third-party API keys and access tokens often stop working on a fixed date. Recording it
+//! lets `secret list`, `project status` and `doctor` warn ahead of time, and `push` refuse
+//! to upload a value that has already expired. The date belongs to the value: writing a
+//! new value clears it, and history keeps each version's date.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::rotation;
+use crate::vault::SecretEntry;
+
+/// Secrets expiring within this many days are reported as expiring soon.
+pub const WARNING_DAYS: i64 = 14;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Expired,
+    ExpiringSoon,
+}
+
+/// Resolve `--expires` (a date, taken as 00:00 UTC, or an RFC 3339 timestamp) or `--ttl`
+/// (days from `now`, e.g. `90d`) into an expiry time.
+pub fn parse(
+    expires: Option<&str>,
+    ttl: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>> {
+    match (expires, ttl) {
+        (Some(_), Some(_)) => bail!("Use either --expires or --ttl, not both"),
+        (Some(date), None) => {
+            let date = date.trim();
+            if let Ok(ts) = DateTime::parse_from_rfc3339(date) {
+                return Ok(Some(ts.with_timezone(&Utc)));
+            }
+            let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .with_context(|| format!("Invalid date '{}'; use YYYY-MM-DD or RFC 3339", date))?;
+            Ok(day.and_hms_opt(0, 0, 0).map(|t| t.and_utc()))
+        }
+        (None, Some(ttl)) => {
+            let days = Duration::days(i64::from(rotation::parse_days(ttl)?));
+            now.checked_add_signed(days)
+                .map(Some)
+                .with_context(|| format!("TTL '{}' reaches past the last supported date", ttl))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Whether `expires_at` has passed or falls within `WARNING_DAYS` of `now`.
+pub fn status(expires_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<Status> {
+    let expires_at = expires_at?;
+    if expires_at <= now {
+        Some(Status::Expired)
+    } else if expires_at - now <= Duration::days(WARNING_DAYS) {
+        Some(Status::ExpiringSoon)
+    } else {
+        None
+    }
+}
+
+/// Short label for plain-text listings, e.g. `expired 2026-01-31` or `expires in 3 days`.
+pub fn describe(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    if expires_at <= now {
+        format!("expired {}", expires_at.format("%Y-%m-%d"))
+    } else {
+        // Round up, so a 5-day TTL set a moment ago still reads "5 days".
+        match ((expires_at - now).num_seconds() + 86_399) / 86_400 {
+            1 => "expires within a day".to_string(),
+            n => format!("expires in {} days", n),
+        }
+    }
+}
+
+/// Keys that have expired and keys expiring soon, each sorted.
+pub fn summarize<'a>(
+    entries: impl IntoIterator<Item = (&'a String, &'a SecretEntry)>,
+    now: DateTime<Utc>,
+) -> (Vec<String>, Vec<String>) {
+    let mut expired = Vec::new();
+    let mut expiring = Vec::new();
+    for (key, entry) in entries {
+        match status(entry.expires_at, now) {
+            Some(Status::Expired) => expired.push(key.clone()),
+            Some(Status::ExpiringSoon) => expiring.push(key.clone()),
+            None => {}
+        }
+    }
+    expired.sort();
+    expiring.sort();
+    (expired, expiring)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expires_and_ttl() {
+        let now = Utc::now();
+        let date = parse(Some("2026-12-31"), None, now).unwrap().unwrap();
+        assert_eq!(date.to_rfc3339(), "2026-12-31T00:00:00+00:00");
+        let ts = parse(Some("2026-12-31T12:00:00+02:00"), None, now)
+            .unwrap()
+            .unwrap();
+        assert_eq!(ts.to_rfc3339(), "2026-12-31T10:00:00+00:00");
+        assert_eq!(
+            parse(None, Some("90d"), now).unwrap(),
+            Some(now + Duration::days(90))
+        );
+        assert_eq!(parse(None, None, now).unwrap(), None);
+        assert!(parse(Some("31/12/2026"), None, now).is_err());
+        assert!(parse(Some("2026-12-31"), Some("90d"), now).is_err());
+        assert!(parse(None, Some("999999y"), now).is_err());
+        assert!(
+            parse(
+                None,
+                Some("100y"),
+                DateTime::<Utc>::MAX_UTC - Duration::days(1)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_status_and_description() {
+        let now = Utc::now();
+        assert_eq!(status(None, now), None);
+        assert_eq!(
+            status(Some(now - Duration::seconds(1)), now),
+            Some(Status::Expired)
+        );
+        assert_eq!(
+            status(Some(now + Duration::days(3)), now),
+            Some(Status::ExpiringSoon)
+        );
+        assert_eq!(status(Some(now + Duration::days(30)), now), None);
+        assert_eq!(
+            describe(now + Duration::days(3) - Duration::hours(1), now),
+            "expires in 3 days"
+        );
+        assert_eq!(
+            describe(now + Duration::hours(2), now),
+            "expires within a day"
+        );
+        assert!(describe(now - Duration::days(1), now).starts_with("expired "));
+    }
+}
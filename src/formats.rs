@@ -0,0 +1,321 @@
+//! File formats for `cred import` / `cred export`: `.env`, JSON, YAML and TOML.
+//!
+//! Structured imports take a map of keys to values; nested objects/tables are flattened by
+//! joining their keys with a separator (`{"db": {"url": ...}}` → `db_url`). Exports write a
+//! flat map, or with `--metadata` (JSON/YAML) a document carrying each entry's format,
+//! description, environment, timestamps and expiry so it re-imports losslessly. History is never
+//! exported.
+
+use crate::envfile::{self, ImportedEntry};
+use crate::error::AppError;
+use crate::fsutil;
+use crate::vault::{SecretEntry, SecretFormat, Vault};
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Separator used to flatten nested keys unless `--separator` says otherwise.
+pub const DEFAULT_SEPARATOR: &str = "_";
+
+/// `kind` marking a metadata export, so it isn't flattened like a plain map.
+const METADATA_KIND: &str = "cred-secrets";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FileFormat {
+    Env,
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl FileFormat {
+    /// Guess the format from the file extension; anything unrecognised is a .env file.
+    pub fn detect(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => FileFormat::Json,
+            "yaml" | "yml" => FileFormat::Yaml,
+            "toml" => FileFormat::Toml,
+            _ => FileFormat::Env,
+        }
+    }
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            FileFormat::Env => "env",
+            FileFormat::Json => "json",
+            FileFormat::Yaml => "yaml",
+            FileFormat::Toml => "toml",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Export document written with `--metadata`.
+#[derive(Serialize, Deserialize)]
+struct Document {
+    api_version: String,
+    kind: String,
+    secrets: BTreeMap<String, DocumentEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DocumentEntry {
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<SecretFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    environment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<&SecretEntry> for DocumentEntry {
+    fn from(entry: &SecretEntry) -> Self {
+        DocumentEntry {
+            value: entry.value.clone(),
+            format: Some(entry.format.clone()),
+            description: entry.description.clone(),
+            environment: entry.environment.clone(),
+            created_at: Some(entry.created_at),
+            updated_at: Some(entry.updated_at),
+            expires_at: entry.expires_at,
+        }
+    }
+}
+
+/// Read entries from `path`, in `format` or the one its extension suggests.
+pub fn parse_file(
+    path: &Path,
+    format: Option<FileFormat>,
+    separator: &str,
+    expand: bool,
+) -> Result<Vec<ImportedEntry>, AppError> {
+    let format = format.unwrap_or_else(|| FileFormat::detect(path));
+    if format == FileFormat::Env {
+        return Ok(envfile::parse_env_file(path, expand)?
+            .into_iter()
+            .map(ImportedEntry::from)
+            .collect());
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_str(&content, format, separator)
+}
+
+/// Parse structured content (see `parse_file`). `.env` content is read without expansion.
+pub fn parse_str(
+    content: &str,
+    format: FileFormat,
+    separator: &str,
+) -> Result<Vec<ImportedEntry>, AppError> {
+    let value: Value = match format {
+        FileFormat::Env => {
+            return Ok(envfile::parse_env_str(content, false)?
+                .into_iter()
+                .map(ImportedEntry::from)
+                .collect());
+        }
+        FileFormat::Json => serde_json::from_str(content).context("Invalid JSON")?,
+        FileFormat::Yaml => serde_yaml_ng::from_str(content).context("Invalid YAML")?,
+        FileFormat::Toml => toml_to_json(toml::Value::Table(
+            content.parse::<toml::Table>().context("Invalid TOML")?,
+        )),
+    };
+
+    if value.get("kind").and_then(Value::as_str) == Some(METADATA_KIND) {
+        let doc: Document =
+            serde_json::from_value(value).context("Invalid cred metadata export")?;
+        return Ok(doc
+            .secrets
+            .into_iter()
+            .map(|(key, e)| ImportedEntry {
+                key,
+                value: e.value,
+                description: e.description,
+                environment: e.environment,
+                format: e.format,
+                created_at: e.created_at,
+                updated_at: e.updated_at,
+                expires_at: e.expires_at,
+            })
+            .collect());
+    }
+
+    let Value::Object(map) = value else {
+        return Err(AppError::user(anyhow!(
+            "Expected a top-level map of keys to values in the {} file",
+            format
+        )));
+    };
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    flatten("", map, separator, &mut entries, &mut seen)?;
+    Ok(entries)
+}
+
+/// Flatten nested maps into `prefix<sep>key` entries. Scalars become strings, arrays are
+/// kept as JSON, nulls are skipped.
+fn flatten(
+    prefix: &str,
+    map: serde_json::Map<String, Value>,
+    separator: &str,
+    entries: &mut Vec<ImportedEntry>,
+    seen: &mut HashSet<String>,
+) -> Result<(), AppError> {
+    for (name, value) in map {
+        let key = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}{}{}", prefix, separator, name)
+        };
+        if key.is_empty() {
+            return Err(AppError::user(anyhow!("Keys cannot be empty")));
+        }
+        let value = match value {
+            Value::Object(nested) => {
+                flatten(&key, nested, separator, entries, seen)?;
+                continue;
+            }
+            Value::Null => continue,
+            Value::String(s) => s,
+            Value::Array(_) => value.to_string(),
+            Value::Bool(_) | Value::Number(_) => value.to_string(),
+        };
+        if !seen.insert(key.clone()) {
+            return Err(AppError::user(anyhow!(
+                "Key '{}' appears more than once after flattening; pick another --separator",
+                key
+            )));
+        }
+        entries.push(ImportedEntry::from((key, value)));
+    }
+    Ok(())
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(f.to_string())),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Export vault contents to `output_path` in `format`. Keys are sorted for stability.
+/// When `keys` is given only those keys are written (missing ones are skipped).
+/// `metadata` (JSON/YAML only) includes each entry's format, description and timestamps.
+/// Refuses to overwrite unless `force` is true. If `dry_run`, no file is created but
+/// overwrite checks still apply.
+pub fn export_file(
+    vault: &Vault,
+    output_path: &Path,
+    keys: Option<&[String]>,
+    format: FileFormat,
+    metadata: bool,
+    force: bool,
+    dry_run: bool,
+) -> Result<usize, AppError> {
+    if output_path.exists() && !force {
+        return Err(AppError::user(anyhow!(
+            "{} exists; rerun with --force to overwrite",
+            output_path.display()
+        )));
+    }
+
+    let mut entries: Vec<_> = vault
+        .effective_entries()
+        .into_iter()
+        .filter(|(k, _)| keys.is_none_or(|ks| ks.contains(k)))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    let body = render(&entries, format, metadata)?;
+
+    if dry_run {
+        return Ok(entries.len());
+    }
+
+    if let Some(parent) = output_path.parent()
+        && !parent.as_os_str().is_empty()
+        && !parent.exists()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fsutil::write_atomic(output_path, body.as_bytes())?;
+
+    Ok(entries.len())
+}
+
+/// Serialize `entries` in `format`: a flat map, or the metadata document with `metadata`.
+pub fn render(
+    entries: &[(&String, &SecretEntry)],
+    format: FileFormat,
+    metadata: bool,
+) -> Result<String, AppError> {
+    if metadata && !matches!(format, FileFormat::Json | FileFormat::Yaml) {
+        return Err(AppError::user(anyhow!(
+            "--metadata is only supported for JSON and YAML exports"
+        )));
+    }
+    Ok(if metadata {
+        let doc = Document {
+            api_version: "1".to_string(),
+            kind: METADATA_KIND.to_string(),
+            secrets: entries
+                .iter()
+                .map(|(k, e)| (k.to_string(), DocumentEntry::from(*e)))
+                .collect(),
+        };
+        match format {
+            FileFormat::Json => format!(
+                "{}\n",
+                serde_json::to_string_pretty(&doc).context("Failed to encode JSON")?
+            ),
+            _ => serde_yaml_ng::to_string(&doc).context("Failed to encode YAML")?,
+        }
+    } else {
+        let flat: BTreeMap<&str, &str> = entries
+            .iter()
+            .map(|(k, e)| (k.as_str(), e.value.as_str()))
+            .collect();
+        match format {
+            FileFormat::Env => envfile::render_env(flat),
+            FileFormat::Json => format!(
+                "{}\n",
+                serde_json::to_string_pretty(&flat).context("Failed to encode JSON")?
+            ),
+            FileFormat::Yaml => serde_yaml_ng::to_string(&flat).context("Failed to encode YAML")?,
+            FileFormat::Toml => toml::to_string(&flat).context("Failed to encode TOML")?,
+        }
+    })
+}
@@ -0,0 +1,285 @@
+//! Crash-safe file writes and advisory locks shared by the vault, config and keystore.
+//!
+//! Writes go to a temporary file in the target's directory, are fsynced, then renamed over
+//! the target, so a crash leaves either the old or the new contents, never a torn file.
+//! Locks are advisory OS locks on a `<file>.lock` sidecar held for a whole
+//! load-modify-save cycle; they are re-entrant within a process so nested loads of the
+//! same file don't deadlock on themselves.
+//!
+//! Files created here are owner-only (0600) on Unix; `cred doctor --fix` tightens older
+//! ones via `make_private`.
+
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long to wait for another cred process to release a lock.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY: Duration = Duration::from_millis(50);
+
+/// Replace `path` with `contents` atomically (temp file + fsync + rename).
+/// New files are owner-only; permissions of an existing file are carried over.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = parent_dir(path);
+    let name = path
+        .file_name()
+        .with_context(|| format!("Invalid path {}", path.display()))?
+        .to_string_lossy();
+    let tmp = dir.join(format!(".{}.tmp-{}", name, std::process::id()));
+
+    let result = (|| -> std::io::Result<()> {
+        let mut file = private_options().write(true).truncate(true).open(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        if let Ok(meta) = fs::metadata(path) {
+            fs::set_permissions(&tmp, meta.permissions())?;
+        }
+        fs::rename(&tmp, path)?;
+        sync_dir(dir);
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result.with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Create `path` owner-only with `contents`, refusing to replace an existing file.
+pub fn write_new(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = match private_options().write(true).create_new(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            bail!("Refusing to overwrite existing file: {}", path.display())
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to create {}", path.display())),
+    };
+    let written = file.write_all(contents).and_then(|_| file.sync_all());
+    if written.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    written.with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Persist the rename itself; best effort (not every platform can open directories).
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(d) = File::open(dir) {
+        let _ = d.sync_all();
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+/// `OpenOptions` that create files readable and writable by the owner only.
+fn private_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.create(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+/// Create a directory only its owner can access.
+pub fn create_private_dir(path: &Path) -> Result<()> {
+    fs::create_dir(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    make_private(path)
+}
+
+/// Whether group/other have no access to `path` (always true off Unix).
+pub fn is_private(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path)
+            .map(|m| m.permissions().mode() & 0o077 == 0)
+            .unwrap_or(true)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        true
+    }
+}
+
+/// Octal permission bits for display (e.g. `644`).
+pub fn mode_string(path: &Path) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path)
+            .map(|m| format!("{:o}", m.permissions().mode() & 0o777))
+            .unwrap_or_default()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        String::new()
+    }
+}
+
+/// Restrict `path` to its owner: 0700 for directories, 0600 for files.
+pub fn make_private(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = if path.is_dir() { 0o700 } else { 0o600 };
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to set permissions on {}", path.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    }
+}
+
+/// Sidecar lock file guarding `path`.
+pub fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// An exclusive advisory lock on a file; released when the last guard for it drops.
+#[derive(Debug)]
+pub struct FileLock {
+    lock_path: PathBuf,
+}
+
+struct Held {
+    file: File,
+    count: usize,
+}
+
+fn held_locks() -> &'static Mutex<HashMap<PathBuf, Held>> {
+    static HELD: OnceLock<Mutex<HashMap<PathBuf, Held>>> = OnceLock::new();
+    HELD.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Take an exclusive lock on `path`, waiting for other processes to release it.
+pub fn lock(path: &Path) -> Result<FileLock> {
+    lock_with_timeout(path, LOCK_TIMEOUT)
+}
+
+pub(crate) fn lock_with_timeout(path: &Path, timeout: Duration) -> Result<FileLock> {
+    let lock_path = lock_path(path);
+    let mut held = held_locks().lock().unwrap();
+    if let Some(h) = held.get_mut(&lock_path) {
+        h.count += 1;
+        return Ok(FileLock { lock_path });
+    }
+
+    let file = private_options()
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open lock file {}", lock_path.display()))?;
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) if start.elapsed() < timeout => {
+                std::thread::sleep(LOCK_RETRY)
+            }
+            Err(TryLockError::WouldBlock) => bail!(
+                "Timed out waiting for {}; another cred process is using it",
+                path.display()
+            ),
+            // Filesystems without lock support: carry on unlocked rather than fail.
+            Err(TryLockError::Error(e)) if e.kind() == ErrorKind::Unsupported => break,
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", lock_path.display()));
+            }
+        }
+    }
+    held.insert(lock_path.clone(), Held { file, count: 1 });
+    Ok(FileLock { lock_path })
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let mut held = held_locks().lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(h) = held.get_mut(&self.lock_path) {
+            h.count -= 1;
+            if h.count == 0
+                && let Some(h) = held.remove(&self.lock_path)
+            {
+                let _ = h.file.unlock();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        write_atomic(&path, b"one").unwrap();
+        write_atomic(&path, b"two").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two");
+        // No temp files left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_new_files_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.enc");
+        write_atomic(&path, b"secret").unwrap();
+        assert!(is_private(&path));
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(!is_private(&path));
+        assert_eq!(mode_string(&path), "644");
+        make_private(&path).unwrap();
+        assert_eq!(mode_string(&path), "600");
+
+        let sub = dir.path().join(".cred");
+        create_private_dir(&sub).unwrap();
+        assert_eq!(mode_string(&sub), "700");
+
+        let share = dir.path().join("share.txt");
+        write_new(&share, b"one").unwrap();
+        assert_eq!(mode_string(&share), "600");
+        assert!(write_new(&share, b"two").is_err());
+        assert_eq!(fs::read_to_string(&share).unwrap(), "one");
+    }
+
+    #[test]
+    fn test_lock_is_reentrant_but_excludes_other_holders() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.enc");
+
+        let outer = lock(&path).unwrap();
+        let inner = lock(&path).unwrap();
+        drop(inner);
+
+        // A separate open file description (as another process would have) is refused.
+        let other = File::options().write(true).open(lock_path(&path)).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+
+        drop(outer);
+        other.try_lock().unwrap();
+        let err = lock_with_timeout(&path, Duration::from_millis(100)).unwrap_err();
+        assert!(err.to_string().contains("another cred process"));
+    }
+}
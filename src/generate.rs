@@ -0,0 +1,408 @@
+//! Secret generation for `cred secret generate`.
+//!
+//! Values are created from the OS CSPRNG straight into the vault, so they never pass
+//! through a clipboard or shell history. A `Recipe` records how a value was made; it is
+//! serializable so the same recipe can be replayed later.
+
+use anyhow::{Result, bail};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64_URL},
+};
+use clap::ValueEnum;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sodiumoxide::crypto::{scalarmult::curve25519, sign::ed25519};
+use std::fmt;
+use zeroize::Zeroize;
+
+use crate::vault::SecretFormat;
+
+/// Suffix of the key holding the public half of a generated key pair (`DEPLOY_KEY_PUBLIC`).
+pub const PUBLIC_KEY_SUFFIX: &str = "_PUBLIC";
+
+/// Symbols used by the `full` charset; quotes, backslash, backtick, `$` and space are
+/// left out so values survive shells and .env files unquoted.
+const SYMBOLS: &str = "!#%&()*+,-./:;<=>?@[]^_{|}~";
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const DIGITS: &str = "0123456789";
+
+/// What to generate (the CLI's `KIND` argument).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Kind {
+    Password,
+    Hex,
+    Base64,
+    Uuid,
+    JwtSecret,
+    Ed25519,
+    Rsa,
+    Wireguard,
+}
+
+/// Characters a password is drawn from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Charset {
+    /// Letters, digits and shell-safe symbols
+    Full,
+    /// Letters and digits
+    Alnum,
+    /// Letters only
+    Alpha,
+    /// Digits only (PINs)
+    Numeric,
+}
+
+impl Charset {
+    /// Character classes; a generated password contains at least one of each.
+    fn classes(self) -> Vec<&'static str> {
+        match self {
+            Charset::Full => vec![UPPER, LOWER, DIGITS, SYMBOLS],
+            Charset::Alnum => vec![UPPER, LOWER, DIGITS],
+            Charset::Alpha => vec![UPPER, LOWER],
+            Charset::Numeric => vec![DIGITS],
+        }
+    }
+}
+
+impl fmt::Display for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Charset::Full => "full",
+            Charset::Alnum => "alnum",
+            Charset::Alpha => "alpha",
+            Charset::Numeric => "numeric",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A fully specified generator.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Recipe {
+    Password { length: usize, charset: Charset },
+    Hex { bytes: usize },
+    Base64 { bytes: usize },
+    Uuid,
+    JwtSecret { bytes: usize },
+    Ed25519,
+    Rsa { bits: usize },
+    Wireguard,
+}
+
+/// A generated value, plus the public half for key pairs.
+pub struct Generated {
+    pub value: String,
+    pub format: SecretFormat,
+    pub public: Option<String>,
+}
+
+impl Drop for Generated {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl Recipe {
+    /// Build a recipe from CLI options, applying per-kind defaults and limits.
+    /// `length` is characters for passwords, bytes for tokens and bits for RSA.
+    pub fn new(kind: Kind, length: Option<usize>, charset: Option<Charset>) -> Result<Self> {
+        if charset.is_some() && kind != Kind::Password {
+            bail!("--charset only applies to passwords");
+        }
+        let ranged = |default: usize, min: usize, max: usize, unit: &str| -> Result<usize> {
+            let n = length.unwrap_or(default);
+            if !(min..=max).contains(&n) {
+                bail!("--length must be between {} and {} {}", min, max, unit);
+            }
+            Ok(n)
+        };
+        Ok(match kind {
+            Kind::Password => Recipe::Password {
+                length: ranged(32, 8, 1024, "characters")?,
+                charset: charset.unwrap_or(Charset::Full),
+            },
+            Kind::Hex => Recipe::Hex {
+                bytes: ranged(32, 8, 1024, "bytes")?,
+            },
+            Kind::Base64 => Recipe::Base64 {
+                bytes: ranged(32, 8, 1024, "bytes")?,
+            },
+            Kind::JwtSecret => Recipe::JwtSecret {
+                bytes: ranged(64, 32, 1024, "bytes")?,
+            },
+            Kind::Rsa => {
+                let bits = length.unwrap_or(3072);
+                if ![2048, 3072, 4096].contains(&bits) {
+                    bail!("RSA keys must be 2048, 3072 or 4096 bits");
+                }
+                Recipe::Rsa { bits }
+            }
+            Kind::Uuid | Kind::Ed25519 | Kind::Wireguard => {
+                if length.is_some() {
+                    bail!("--length does not apply to this kind of secret");
+                }
+                match kind {
+                    Kind::Uuid => Recipe::Uuid,
+                    Kind::Ed25519 => Recipe::Ed25519,
+                    _ => Recipe::Wireguard,
+                }
+            }
+        })
+    }
+
+    /// Human-readable account of how the value was made, used as its description.
+    pub fn describe(&self) -> String {
+        match self {
+            Recipe::Password { length, charset } => {
+                format!("Generated password ({} chars, {})", length, charset)
+            }
+            Recipe::Hex { bytes } => format!("Generated {}-byte hex token", bytes),
+            Recipe::Base64 { bytes } => format!("Generated {}-byte base64 token", bytes),
+            Recipe::Uuid => "Generated UUID v4".to_string(),
+            Recipe::JwtSecret { bytes } => {
+                format!("Generated {}-byte JWT HMAC secret (base64url)", bytes)
+            }
+            Recipe::Ed25519 => "Generated Ed25519 private key (PKCS#8 PEM)".to_string(),
+            Recipe::Rsa { bits } => format!("Generated RSA-{} private key (PKCS#8 PEM)", bits),
+            Recipe::Wireguard => "Generated WireGuard private key".to_string(),
+        }
+    }
+
+    /// Produce a fresh value.
+    pub fn generate(&self) -> Result<Generated> {
+        Ok(match self {
+            Recipe::Password { length, charset } => Generated {
+                value: password(*length, *charset),
+                format: SecretFormat::Raw,
+                public: None,
+            },
+            Recipe::Hex { bytes } => Generated {
+                value: with_random(*bytes, hex),
+                format: SecretFormat::Raw,
+                public: None,
+            },
+            Recipe::Base64 { bytes } => Generated {
+                value: with_random(*bytes, |b| BASE64.encode(b)),
+                format: SecretFormat::Base64,
+                public: None,
+            },
+            Recipe::Uuid => Generated {
+                value: uuid::Uuid::new_v4().to_string(),
+                format: SecretFormat::Raw,
+                public: None,
+            },
+            Recipe::JwtSecret { bytes } => Generated {
+                value: with_random(*bytes, |b| BASE64_URL.encode(b)),
+                format: SecretFormat::Base64,
+                public: None,
+            },
+            Recipe::Ed25519 => ed25519_pair()?,
+            Recipe::Rsa { bits } => rsa_pair(*bits)?,
+            Recipe::Wireguard => wireguard_pair()?,
+        })
+    }
+}
+
+/// Fill `n` random bytes, encode them and wipe the buffer.
+fn with_random(n: usize, encode: impl Fn(&[u8]) -> String) -> String {
+    let mut bytes = vec![0u8; n];
+    rand::rng().fill_bytes(&mut bytes);
+    let out = encode(&bytes);
+    bytes.zeroize();
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Uniformly random characters from `charset`, retried until every class appears.
+fn password(length: usize, charset: Charset) -> String {
+    let classes = charset.classes();
+    let alphabet: Vec<char> = classes.concat().chars().collect();
+    let mut rng = rand::rng();
+    loop {
+        let candidate: String = (0..length)
+            .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+            .collect();
+        if classes
+            .iter()
+            .all(|class| candidate.chars().any(|c| class.contains(c)))
+        {
+            return candidate;
+        }
+    }
+}
+
+/// PEM armour with 64-column base64 lines.
+fn pem(label: &str, der: &[u8]) -> String {
+    let b64 = BASE64.encode(der);
+    let mut out = format!("-----BEGIN {}-----\n", label);
+    for chunk in b64.as_bytes().chunks(64) {
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push('\n');
+    }
+    out.push_str(&format!("-----END {}-----\n", label));
+    out
+}
+
+/// DER prefixes for Ed25519 keys (RFC 8410): PKCS#8 private key and SPKI public key.
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+fn ed25519_pair() -> Result<Generated> {
+    init_sodium()?;
+    let mut seed = [0u8; 32];
+    rand::rng().fill_bytes(&mut seed);
+    let (public, _secret) = ed25519::keypair_from_seed(&ed25519::Seed(seed));
+
+    let mut der = ED25519_PKCS8_PREFIX.to_vec();
+    der.extend_from_slice(&seed);
+    seed.zeroize();
+    let value = pem("PRIVATE KEY", &der);
+    der.zeroize();
+
+    let mut spki = ED25519_SPKI_PREFIX.to_vec();
+    spki.extend_from_slice(&public.0);
+    Ok(Generated {
+        value,
+        format: SecretFormat::Pem,
+        public: Some(pem("PUBLIC KEY", &spki)),
+    })
+}
+
+fn rsa_pair(bits: usize) -> Result<Generated> {
+    use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+
+    let mut rng = chacha20poly1305::aead::OsRng;
+    let key = rsa::RsaPrivateKey::new(&mut rng, bits)?;
+    let value = key.to_pkcs8_pem(LineEnding::LF)?.to_string();
+    let public = key.to_public_key().to_public_key_pem(LineEnding::LF)?;
+    Ok(Generated {
+        value,
+        format: SecretFormat::Pem,
+        public: Some(public),
+    })
+}
+
+/// WireGuard keys are base64 X25519 keys; the private scalar is clamped as `wg genkey` does.
+fn wireguard_pair() -> Result<Generated> {
+    init_sodium()?;
+    let mut private = [0u8; 32];
+    rand::rng().fill_bytes(&mut private);
+    private[0] &= 248;
+    private[31] &= 127;
+    private[31] |= 64;
+    let public = curve25519::scalarmult_base(&curve25519::Scalar(private));
+    let value = BASE64.encode(private);
+    private.zeroize();
+    Ok(Generated {
+        value,
+        format: SecretFormat::Base64,
+        public: Some(BASE64.encode(public.0)),
+    })
+}
+
+fn init_sodium() -> Result<()> {
+    sodiumoxide::init().map_err(|_| anyhow::anyhow!("Failed to initialize sodiumoxide"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recipe_defaults_and_limits() {
+        assert_eq!(
+            Recipe::new(Kind::Password, None, None).unwrap(),
+            Recipe::Password {
+                length: 32,
+                charset: Charset::Full
+            }
+        );
+        assert_eq!(
+            Recipe::new(Kind::Rsa, None, None).unwrap(),
+            Recipe::Rsa { bits: 3072 }
+        );
+        assert!(Recipe::new(Kind::Password, Some(4), None).is_err());
+        assert!(Recipe::new(Kind::Hex, None, Some(Charset::Alnum)).is_err());
+        assert!(Recipe::new(Kind::Rsa, Some(1024), None).is_err());
+        assert!(Recipe::new(Kind::Uuid, Some(16), None).is_err());
+
+        let recipe = Recipe::new(Kind::JwtSecret, Some(48), None).unwrap();
+        let json = serde_json::to_string(&recipe).unwrap();
+        assert_eq!(json, r#"{"kind":"jwt-secret","bytes":48}"#);
+        assert_eq!(serde_json::from_str::<Recipe>(&json).unwrap(), recipe);
+    }
+
+    #[test]
+    fn test_generated_tokens_match_their_recipe() {
+        let pw = Recipe::new(Kind::Password, Some(12), Some(Charset::Alnum))
+            .unwrap()
+            .generate()
+            .unwrap();
+        assert_eq!(pw.value.len(), 12);
+        assert!(pw.value.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert!(pw.value.chars().any(|c| c.is_ascii_digit()));
+
+        let pin = password(8, Charset::Numeric);
+        assert!(pin.chars().all(|c| c.is_ascii_digit()));
+
+        let hex = Recipe::Hex { bytes: 16 }.generate().unwrap();
+        assert_eq!(hex.value.len(), 32);
+        assert!(hex.value.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let b64 = Recipe::Base64 { bytes: 24 }.generate().unwrap();
+        assert_eq!(BASE64.decode(&b64.value).unwrap().len(), 24);
+        assert_eq!(b64.format, SecretFormat::Base64);
+
+        let jwt = Recipe::JwtSecret { bytes: 64 }.generate().unwrap();
+        assert_eq!(BASE64_URL.decode(&jwt.value).unwrap().len(), 64);
+
+        let id = Recipe::Uuid.generate().unwrap();
+        assert!(uuid::Uuid::parse_str(&id.value).is_ok());
+    }
+
+    #[test]
+    fn test_key_pairs_are_consistent() {
+        let ed = Recipe::Ed25519.generate().unwrap();
+        assert_eq!(ed.format, SecretFormat::Pem);
+        let body: String = ed
+            .value
+            .lines()
+            .filter(|l| !l.starts_with("-----"))
+            .collect();
+        let der = BASE64.decode(body).unwrap();
+        assert_eq!(&der[..16], &ED25519_PKCS8_PREFIX);
+        let seed = ed25519::Seed::from_slice(&der[16..]).unwrap();
+        let (public, _) = ed25519::keypair_from_seed(&seed);
+        let public_pem = ed.public.as_deref().unwrap();
+        let spki: String = public_pem
+            .lines()
+            .filter(|l| !l.starts_with("-----"))
+            .collect();
+        assert_eq!(&BASE64.decode(spki).unwrap()[12..], &public.0);
+
+        let wg = Recipe::Wireguard.generate().unwrap();
+        let private = BASE64.decode(&wg.value).unwrap();
+        assert_eq!(private[0] & 7, 0);
+        let expected =
+            curve25519::scalarmult_base(&curve25519::Scalar::from_slice(&private).unwrap());
+        assert_eq!(wg.public.as_deref().unwrap(), BASE64.encode(expected.0));
+
+        let rsa_key = Recipe::Rsa { bits: 2048 }.generate().unwrap();
+        use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+        let private = rsa::RsaPrivateKey::from_pkcs8_pem(&rsa_key.value).unwrap();
+        let public =
+            rsa::RsaPublicKey::from_public_key_pem(rsa_key.public.as_deref().unwrap()).unwrap();
+        assert_eq!(private.to_public_key(), public);
+    }
+}
@@ -0,0 +1,238 @@
+//! Bitwarden import source.
+//! Reads items from `bw get item`/`bw list items` or an unencrypted JSON vault export.
+
+use super::{ImportOptions, field_key, read_export, run_cli};
+use crate::envfile::ImportedEntry;
+use crate::error::AppError;
+use anyhow::{Context, anyhow};
+use serde::Deserialize;
+
+/// Bitwarden custom field type for values linked to other fields (no value of their own).
+const FIELD_TYPE_LINKED: u8 = 3;
+
+/// Unencrypted `bw export --format json` document.
+#[derive(Deserialize, Debug)]
+struct Export {
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+/// Subset of a Bitwarden item shared by the CLI and export formats.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    id: String,
+    name: String,
+    #[serde(default)]
+    folder_id: Option<String>,
+    #[serde(default)]
+    login: Option<Login>,
+    #[serde(default)]
+    fields: Option<Vec<Field>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Login {
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Field {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(default, rename = "type")]
+    kind: u8,
+}
+
+pub fn load(opts: &ImportOptions) -> Result<Vec<ImportedEntry>, AppError> {
+    let (items, folder_id) = match opts.path {
+        Some(path) => {
+            let export: Export = serde_json::from_str(&read_export(path)?).context(
+                "Unexpected Bitwarden export format (encrypted exports are not supported)",
+            )?;
+            let folder_id = match opts.vault {
+                Some(name) => Some(resolve_folder(&export.folders, name)?),
+                None => None,
+            };
+            (export.items, folder_id)
+        }
+        None => fetch_items(opts)?,
+    };
+    let selected: Vec<&Item> = items
+        .iter()
+        .filter(|item| {
+            opts.item
+                .is_none_or(|name| item.name == name || item.id == name)
+        })
+        .filter(|item| {
+            folder_id
+                .as_ref()
+                .is_none_or(|id| item.folder_id.as_ref() == Some(id))
+        })
+        .collect();
+    if selected.is_empty() {
+        return Err(AppError::user(anyhow!("No matching Bitwarden items found")));
+    }
+    let prefixed = opts.item.is_none();
+    Ok(selected
+        .into_iter()
+        .flat_map(|item| item_entries(item, prefixed))
+        .collect())
+}
+
+/// Query the `bw` CLI (using the caller's `BW_SESSION`) for one item or a folder's items.
+fn fetch_items(opts: &ImportOptions) -> Result<(Vec<Item>, Option<String>), AppError> {
+    let folder_id = match opts.vault {
+        Some(name) => {
+            let folders: Vec<Folder> =
+                serde_json::from_str(&run_cli("bw", &["list", "folders", "--search", name])?)
+                    .context("Unexpected `bw list folders` output")?;
+            Some(resolve_folder(&folders, name)?)
+        }
+        None => None,
+    };
+    let items = match (opts.item, folder_id.as_deref()) {
+        (Some(item), _) => vec![
+            serde_json::from_str(&run_cli("bw", &["get", "item", item])?)
+                .context("Unexpected `bw get item` output")?,
+        ],
+        (None, Some(folder)) => {
+            serde_json::from_str(&run_cli("bw", &["list", "items", "--folderid", folder])?)
+                .context("Unexpected `bw list items` output")?
+        }
+        (None, None) => {
+            return Err(AppError::user(anyhow!(
+                "Bitwarden import requires --item or --vault (folder name)"
+            )));
+        }
+    };
+    Ok((items, folder_id))
+}
+
+fn resolve_folder(folders: &[Folder], name: &str) -> Result<String, AppError> {
+    folders
+        .iter()
+        .find(|f| f.name == name)
+        .map(|f| f.id.clone())
+        .ok_or_else(|| AppError::user(anyhow!("Bitwarden folder '{}' not found", name)))
+}
+
+/// Map login credentials and custom fields to vault keys; field names become descriptions.
+/// With `prefixed`, keys and descriptions start with the item name.
+fn item_entries(item: &Item, prefixed: bool) -> Vec<ImportedEntry> {
+    let title = prefixed.then_some(item.name.as_str());
+    let login = item.login.as_ref();
+    let login_fields = [
+        ("username", login.and_then(|l| l.username.as_deref())),
+        ("password", login.and_then(|l| l.password.as_deref())),
+    ];
+    let custom = item
+        .fields
+        .iter()
+        .flatten()
+        .filter(|f| f.kind != FIELD_TYPE_LINKED)
+        .filter_map(|f| Some((f.name.as_deref()?, f.value.as_deref())));
+
+    login_fields
+        .into_iter()
+        .chain(custom)
+        .filter_map(|(label, value)| {
+            let value = value.filter(|v| !v.is_empty())?;
+            let (key, description) = field_key(title, label)?;
+            Some(ImportedEntry {
+                key,
+                value: value.to_string(),
+                description: Some(description),
+                environment: None,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+        "encrypted": false,
+        "folders": [{"id": "f-dev", "name": "Dev"}],
+        "items": [
+            {
+                "id": "i1", "folderId": "f-dev", "type": 1, "name": "API Keys",
+                "login": {"username": "deploy", "password": "hunter2", "totp": null},
+                "fields": [
+                    {"name": "Stripe Key", "value": "sk_test_1", "type": 1},
+                    {"name": "Linked", "value": null, "type": 3}
+                ]
+            },
+            {"id": "i2", "folderId": null, "type": 2, "name": "Loose note", "notes": "x"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_export_maps_login_and_custom_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bw.json");
+        std::fs::write(&path, EXPORT).unwrap();
+
+        let opts = ImportOptions {
+            path: Some(&path),
+            vault: Some("Dev"),
+            item: None,
+            ..Default::default()
+        };
+        let entries = load(&opts).unwrap();
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "API_KEYS_USERNAME",
+                "API_KEYS_PASSWORD",
+                "API_KEYS_STRIPE_KEY"
+            ]
+        );
+        assert_eq!(
+            entries[2].description.as_deref(),
+            Some("API Keys: Stripe Key")
+        );
+
+        let opts = ImportOptions {
+            path: Some(&path),
+            item: Some("API Keys"),
+            ..Default::default()
+        };
+        let entries = load(&opts).unwrap();
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["USERNAME", "PASSWORD", "STRIPE_KEY"]);
+        assert_eq!(entries[2].description.as_deref(), Some("Stripe Key"));
+    }
+
+    #[test]
+    fn test_unknown_folder_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bw.json");
+        std::fs::write(&path, EXPORT).unwrap();
+
+        let opts = ImportOptions {
+            path: Some(&path),
+            vault: Some("Prod"),
+            item: None,
+            ..Default::default()
+        };
+        assert!(load(&opts).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+//! Doppler import source.
+//! Accepts `doppler secrets --json`, `doppler secrets download --format json|env` output or files.
+
+use super::{ImportOptions, read_export, run_cli};
+use crate::envfile::{self, ImportedEntry};
+use crate::error::AppError;
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Doppler injects these into every download; they describe the config, not secrets.
+const META_KEYS: [&str; 3] = ["DOPPLER_PROJECT", "DOPPLER_ENVIRONMENT", "DOPPLER_CONFIG"];
+
+/// A secret as emitted by either `secrets --json` (object) or `secrets download` (string).
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum DopplerValue {
+    Plain(String),
+    Detailed {
+        #[serde(default)]
+        computed: Option<String>,
+        #[serde(default)]
+        raw: Option<String>,
+        #[serde(default)]
+        note: Option<String>,
+    },
+}
+
+pub fn load(opts: &ImportOptions) -> Result<Vec<ImportedEntry>, AppError> {
+    let content = match opts.path {
+        Some(path) => read_export(path)?,
+        None => {
+            let mut args = vec!["secrets", "--json"];
+            if let Some(env) = opts.environment {
+                args.extend(["--config", env]);
+            }
+            run_cli("doppler", &args)?
+        }
+    };
+    parse(&content, opts.environment)
+}
+
+fn parse(content: &str, environment: Option<&str>) -> Result<Vec<ImportedEntry>, AppError> {
+    let rows: Vec<(String, String, Option<String>)> = if content.trim_start().starts_with('{') {
+        let map: BTreeMap<String, DopplerValue> =
+            serde_json::from_str(content).context("Unexpected Doppler JSON format")?;
+        map.into_iter()
+            .filter_map(|(key, value)| match value {
+                DopplerValue::Plain(v) => Some((key, v, None)),
+                DopplerValue::Detailed {
+                    computed,
+                    raw,
+                    note,
+                } => Some((key, computed.or(raw)?, note.filter(|n| !n.is_empty()))),
+            })
+            .collect()
+    } else {
+        envfile::parse_env_str(content, false)?
+            .into_iter()
+            .map(|(k, v)| (k, v, None))
+            .collect()
+    };
+
+    let detected_env = rows
+        .iter()
+        .find(|(k, _, _)| k == "DOPPLER_ENVIRONMENT")
+        .map(|(_, v, _)| v.clone());
+    let environment = environment.map(str::to_string).or(detected_env);
+
+    Ok(rows
+        .into_iter()
+        .filter(|(key, _, _)| !META_KEYS.contains(&key.as_str()))
+        .map(|(key, value, description)| ImportedEntry {
+            key,
+            value,
+            description,
+            environment: environment.clone(),
+            ..Default::default()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detailed_json_keeps_notes_and_environment() {
+        let content = r#"{
+            "API_KEY": {"computed": "abc", "raw": "abc", "note": "Primary API key"},
+            "DB_URL": {"computed": "postgres://db", "raw": "postgres://${HOST}"},
+            "DOPPLER_ENVIRONMENT": {"computed": "prd", "raw": "prd"},
+            "DOPPLER_PROJECT": {"computed": "web", "raw": "web"}
+        }"#;
+        let entries = parse(content, None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "API_KEY");
+        assert_eq!(entries[0].description.as_deref(), Some("Primary API key"));
+        assert_eq!(entries[1].value, "postgres://db");
+        assert!(
+            entries
+                .iter()
+                .all(|e| e.environment.as_deref() == Some("prd"))
+        );
+    }
+
+    #[test]
+    fn test_download_formats() {
+        let json = r#"{"TOKEN": "t1", "DOPPLER_CONFIG": "dev"}"#;
+        let entries = parse(json, Some("dev")).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value, "t1");
+        assert_eq!(entries[0].environment.as_deref(), Some("dev"));
+
+        let env = "TOKEN=\"t1\"\nDOPPLER_ENVIRONMENT=\"stg\"\n";
+        let entries = parse(env, None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value, "t1");
+        assert_eq!(entries[0].environment.as_deref(), Some("stg"));
+    }
+}
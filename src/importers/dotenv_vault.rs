@@ -0,0 +1,201 @@
+//! dotenv-vault import source.
+//! Decrypts a `.env.vault` file with a `DOTENV_KEY` (AES-256-GCM, nonce-prefixed ciphertext).
+
+use super::{ImportOptions, read_export};
+use crate::envfile::{self, ImportedEntry};
+use crate::error::AppError;
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, KeyInit},
+};
+use anyhow::{Context, anyhow};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use std::path::Path;
+
+/// Default vault file name written by `npx dotenv-vault build`.
+const DEFAULT_VAULT_FILE: &str = ".env.vault";
+const NONCE_LEN: usize = 12;
+
+/// A parsed `DOTENV_KEY` URI: `dotenv://:key_<hex>@dotenv.org/vault/.env.vault?environment=<env>`.
+struct DotenvKey {
+    key: [u8; 32],
+    environment: String,
+}
+
+pub fn load(opts: &ImportOptions) -> Result<Vec<ImportedEntry>, AppError> {
+    let path = opts.path.unwrap_or_else(|| Path::new(DEFAULT_VAULT_FILE));
+    let dotenv_key = match opts.dotenv_key {
+        Some(k) => k.to_string(),
+        None => std::env::var("DOTENV_KEY").map_err(|_| {
+            AppError::user(anyhow!(
+                "dotenv-vault import requires the DOTENV_KEY environment variable or --dotenv-key-stdin"
+            ))
+        })?,
+    };
+    decrypt_vault(&read_export(path)?, &dotenv_key)
+}
+
+/// Try each comma-separated key until one decrypts its environment's payload.
+fn decrypt_vault(vault_content: &str, dotenv_keys: &str) -> Result<Vec<ImportedEntry>, AppError> {
+    let payloads = envfile::parse_env_str(vault_content, false)?;
+    let mut last_err = anyhow!("DOTENV_KEY is empty");
+    for raw_key in dotenv_keys
+        .split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+    {
+        let attempt = parse_key(raw_key).and_then(|key| {
+            let var = format!("DOTENV_VAULT_{}", key.environment.to_uppercase());
+            let payload = payloads
+                .iter()
+                .find(|(k, _)| *k == var)
+                .map(|(_, v)| v)
+                .ok_or_else(|| anyhow!("{} not found in .env.vault", var))?;
+            let plaintext = decrypt_payload(&key.key, payload)?;
+            Ok((key.environment, plaintext))
+        });
+        match attempt {
+            Ok((environment, plaintext)) => {
+                return Ok(envfile::parse_env_str(&plaintext, false)?
+                    .into_iter()
+                    .map(|(key, value)| ImportedEntry {
+                        key,
+                        value,
+                        description: None,
+                        environment: Some(environment.clone()),
+                        ..Default::default()
+                    })
+                    .collect());
+            }
+            Err(e) => last_err = e,
+        }
+    }
+    Err(AppError::user(last_err))
+}
+
+fn parse_key(uri: &str) -> anyhow::Result<DotenvKey> {
+    let rest = uri
+        .strip_prefix("dotenv://")
+        .ok_or_else(|| anyhow!("Invalid DOTENV_KEY: expected a dotenv:// URI"))?;
+    let (userinfo, location) = rest
+        .split_once('@')
+        .ok_or_else(|| anyhow!("Invalid DOTENV_KEY: missing key"))?;
+    let password = userinfo.rsplit(':').next().unwrap_or_default();
+    let hex = password.strip_prefix("key_").unwrap_or(password);
+    // Check the whole key first so the byte slicing below stays on ASCII boundaries.
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        anyhow::bail!("Invalid DOTENV_KEY: key must be 64 hex characters");
+    }
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .context("Invalid DOTENV_KEY: key is not hex")?;
+    }
+    let environment = location
+        .split_once('?')
+        .and_then(|(_, query)| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("environment="))
+        })
+        .filter(|env| !env.is_empty())
+        .ok_or_else(|| anyhow!("Invalid DOTENV_KEY: missing environment parameter"))?;
+    Ok(DotenvKey {
+        key,
+        environment: environment.to_string(),
+    })
+}
+
+fn decrypt_payload(key: &[u8; 32], payload_b64: &str) -> anyhow::Result<String> {
+    let data = BASE64
+        .decode(payload_b64.trim())
+        .context("Invalid base64 in .env.vault payload")?;
+    if data.len() <= NONCE_LEN {
+        anyhow::bail!(".env.vault payload is too short");
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Decryption failed; check DOTENV_KEY"))?;
+    String::from_utf8(plaintext).context(".env.vault payload is not UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX_KEY: &str = "ddcaa26504cd70a6fef9801901c3981538563a1767c297cb8416e8a38c62fe00";
+
+    fn encrypt(plaintext: &str) -> String {
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&HEX_KEY[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let nonce = [7u8; NONCE_LEN];
+        let mut data = nonce.to_vec();
+        data.extend(
+            cipher
+                .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+                .unwrap(),
+        );
+        BASE64.encode(data)
+    }
+
+    #[test]
+    fn test_decrypts_matching_environment() {
+        let vault = format!(
+            "# .env.vault (generated)\nDOTENV_VAULT_PRODUCTION=\"{}\"\n",
+            encrypt("API_KEY=\"prod-key\"\nDEBUG=false\n")
+        );
+        let uri = format!(
+            "dotenv://:key_{}@dotenv.org/vault/.env.vault?environment=production",
+            HEX_KEY
+        );
+        let entries = decrypt_vault(&vault, &uri).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "API_KEY");
+        assert_eq!(entries[0].value, "prod-key");
+        assert_eq!(entries[0].environment.as_deref(), Some("production"));
+    }
+
+    #[test]
+    fn test_wrong_key_or_environment_fails() {
+        let vault = format!("DOTENV_VAULT_PRODUCTION=\"{}\"\n", encrypt("A=1\n"));
+        let missing_env = format!(
+            "dotenv://:key_{}@dotenv.org/vault/.env.vault?environment=staging",
+            HEX_KEY
+        );
+        assert!(decrypt_vault(&vault, &missing_env).is_err());
+
+        let wrong_key = format!(
+            "dotenv://:key_{}@dotenv.org/vault/.env.vault?environment=production",
+            "00".repeat(32)
+        );
+        assert!(decrypt_vault(&vault, &wrong_key).is_err());
+        assert!(decrypt_vault(&vault, "not-a-uri").is_err());
+    }
+
+    #[test]
+    fn test_malformed_keys_are_rejected() {
+        let uri = |key: &str| {
+            format!(
+                "dotenv://:key_{}@dotenv.org/vault/.env.vault?environment=production",
+                key
+            )
+        };
+        assert!(parse_key(&uri(HEX_KEY)).is_ok());
+        // One multibyte character in place of two hex digits keeps the byte length at 64.
+        let multibyte = format!("é{}", &HEX_KEY[2..]);
+        assert_eq!(multibyte.len(), 64);
+        for bad in [
+            multibyte,
+            format!("00{}", HEX_KEY),
+            HEX_KEY[2..].to_string(),
+            format!("+f{}", &HEX_KEY[2..]),
+        ] {
+            assert!(parse_key(&uri(&bad)).is_err(), "{}", bad);
+        }
+    }
+}
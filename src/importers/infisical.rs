@@ -0,0 +1,88 @@
+//! Infisical import source.
+//! Accepts `infisical export --format json|dotenv` output or files.
+
+use super::{ImportOptions, read_export, run_cli};
+use crate::envfile::{self, ImportedEntry};
+use crate::error::AppError;
+use anyhow::Context;
+use serde::Deserialize;
+
+/// A secret row from `infisical export --format json`.
+#[derive(Deserialize, Debug)]
+struct Row {
+    #[serde(alias = "secretKey")]
+    key: String,
+    #[serde(alias = "secretValue")]
+    value: String,
+    #[serde(default, alias = "secretComment")]
+    comment: Option<String>,
+    #[serde(default)]
+    environment: Option<String>,
+}
+
+pub fn load(opts: &ImportOptions) -> Result<Vec<ImportedEntry>, AppError> {
+    let content = match opts.path {
+        Some(path) => read_export(path)?,
+        None => {
+            let env_flag = opts.environment.map(|env| format!("--env={}", env));
+            let mut args = vec!["export", "--format=json"];
+            if let Some(flag) = env_flag.as_deref() {
+                args.push(flag);
+            }
+            run_cli("infisical", &args)?
+        }
+    };
+    parse(&content, opts.environment)
+}
+
+fn parse(content: &str, environment: Option<&str>) -> Result<Vec<ImportedEntry>, AppError> {
+    if content.trim_start().starts_with('[') {
+        let rows: Vec<Row> =
+            serde_json::from_str(content).context("Unexpected Infisical JSON format")?;
+        return Ok(rows
+            .into_iter()
+            .map(|row| ImportedEntry {
+                key: row.key,
+                value: row.value,
+                description: row.comment.filter(|c| !c.is_empty()),
+                environment: environment.map(str::to_string).or(row.environment),
+                ..Default::default()
+            })
+            .collect());
+    }
+    Ok(envfile::parse_env_str(content, false)?
+        .into_iter()
+        .map(|(key, value)| ImportedEntry {
+            key,
+            value,
+            description: None,
+            environment: environment.map(str::to_string),
+            ..Default::default()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_export_maps_comments() {
+        let content = r#"[
+            {"key": "STRIPE_KEY", "value": "sk_1", "type": "shared", "comment": "Billing"},
+            {"secretKey": "DB_URL", "secretValue": "postgres://db", "secretComment": ""}
+        ]"#;
+        let entries = parse(content, Some("prod")).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].description.as_deref(), Some("Billing"));
+        assert!(entries[1].description.is_none());
+        assert_eq!(entries[1].environment.as_deref(), Some("prod"));
+    }
+
+    #[test]
+    fn test_dotenv_export() {
+        let entries = parse("A='1'\nB=two\n", None).unwrap();
+        assert_eq!(entries[0].value, "1");
+        assert_eq!(entries[1].value, "two");
+    }
+}
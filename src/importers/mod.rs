@@ -0,0 +1,228 @@
+//! Import source registry for `cred import`.
+//! Each source turns external data (a .env file, a password manager) into `ImportedEntry` rows.
+
+mod bitwarden;
+mod doppler;
+mod dotenv_vault;
+mod infisical;
+mod onepassword;
+
+use crate::envfile::ImportedEntry;
+use crate::error::AppError;
+use crate::formats::{self, FileFormat};
+use anyhow::{Context, anyhow};
+use clap::ValueEnum;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+/// Supported import sources.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ImportSource {
+    /// `.env`-style `KEY=VALUE` file
+    #[default]
+    Env,
+    /// 1Password via the `op` CLI or its JSON item output
+    #[value(name = "1password")]
+    OnePassword,
+    /// Bitwarden via the `bw` CLI or its unencrypted JSON export
+    Bitwarden,
+    /// Doppler via the `doppler` CLI or a JSON/env download
+    Doppler,
+    /// Infisical via the `infisical` CLI or a JSON/dotenv export
+    Infisical,
+    /// dotenv-vault `.env.vault` decrypted with a `DOTENV_KEY`
+    #[value(name = "dotenv-vault")]
+    DotenvVault,
+}
+
+impl fmt::Display for ImportSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ImportSource::Env => "env",
+            ImportSource::OnePassword => "1password",
+            ImportSource::Bitwarden => "bitwarden",
+            ImportSource::Doppler => "doppler",
+            ImportSource::Infisical => "infisical",
+            ImportSource::DotenvVault => "dotenv-vault",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Selection options shared by password manager sources.
+#[derive(Debug, Default)]
+pub struct ImportOptions<'a> {
+    /// Export file to read instead of invoking the source CLI.
+    pub path: Option<&'a Path>,
+    /// Vault (1Password) or folder (Bitwarden) to read from.
+    pub vault: Option<&'a str>,
+    /// Single item to import; all items in `vault` when omitted.
+    pub item: Option<&'a str>,
+    /// Source environment/config to read (Doppler, Infisical); recorded on each entry.
+    pub environment: Option<&'a str>,
+    /// `DOTENV_KEY` URI(s) for dotenv-vault read from stdin; falls back to the environment variable.
+    pub dotenv_key: Option<&'a str>,
+    /// Expand `${VAR}` references in `.env` values.
+    pub expand: bool,
+    /// Format of the file at `path` (`--from env` only); detected from the extension if unset.
+    pub format: Option<FileFormat>,
+    /// Separator for flattening nested JSON/YAML/TOML keys.
+    pub separator: Option<&'a str>,
+}
+
+/// Read entries from the selected source. `.env` imports require a path; other
+/// sources read an export file when one is given, otherwise shell out to their CLI.
+pub fn load(source: ImportSource, opts: &ImportOptions) -> Result<Vec<ImportedEntry>, AppError> {
+    if opts.format.is_some() && source != ImportSource::Env {
+        return Err(AppError::user(anyhow!(
+            "--format only applies to file imports (--from env)"
+        )));
+    }
+    let entries = match source {
+        ImportSource::Env => {
+            let path = opts
+                .path
+                .ok_or_else(|| anyhow!("Importing from a .env file requires a path"))?;
+            formats::parse_file(
+                path,
+                opts.format,
+                opts.separator.unwrap_or(formats::DEFAULT_SEPARATOR),
+                opts.expand,
+            )?
+        }
+        ImportSource::OnePassword => onepassword::load(opts)?,
+        ImportSource::Bitwarden => bitwarden::load(opts)?,
+        ImportSource::Doppler => doppler::load(opts)?,
+        ImportSource::Infisical => infisical::load(opts)?,
+        ImportSource::DotenvVault => dotenv_vault::load(opts)?,
+    };
+    if source != ImportSource::Env {
+        ensure_unique_keys(&entries)?;
+    }
+    Ok(entries)
+}
+
+/// Normalize a field label into an environment-style key (`API Key` → `API_KEY`).
+pub fn normalize_key(label: &str) -> Option<String> {
+    let mut key = String::new();
+    for c in label.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            key.push(c.to_ascii_uppercase());
+        } else if !key.is_empty() && !key.ends_with('_') {
+            key.push('_');
+        }
+    }
+    let key = key.trim_end_matches('_').to_string();
+    if key.is_empty() {
+        return None;
+    }
+    if key.starts_with(|c: char| c.is_ascii_digit()) {
+        return Some(format!("_{}", key));
+    }
+    Some(key)
+}
+
+/// Key and description for a password manager field. Whole-vault imports prefix both with
+/// the item title, since items commonly share labels like `username` and `password`.
+fn field_key(item_title: Option<&str>, label: &str) -> Option<(String, String)> {
+    match item_title {
+        Some(title) => Some((
+            normalize_key(&format!("{} {}", title, label))?,
+            format!("{}: {}", title, label),
+        )),
+        None => Some((normalize_key(label)?, label.to_string())),
+    }
+}
+
+/// Refuse ambiguous imports where two fields map to the same vault key.
+fn ensure_unique_keys(entries: &[ImportedEntry]) -> Result<(), AppError> {
+    let mut seen = std::collections::HashSet::new();
+    for entry in entries {
+        if !seen.insert(entry.key.as_str()) {
+            return Err(AppError::user(anyhow!(
+                "Multiple fields map to key '{}'; narrow the import with --item",
+                entry.key
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Read an export file into a string.
+fn read_export(path: &Path) -> Result<String, AppError> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content)
+}
+
+/// Run a password manager CLI and capture stdout, surfacing stderr on failure.
+fn run_cli(program: &str, args: &[&str]) -> Result<String, AppError> {
+    let output = Command::new(program).args(args).output().map_err(|e| {
+        AppError::user(anyhow!(
+            "Failed to run `{}` ({}). Install it or pass an export file path.",
+            program,
+            e
+        ))
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::auth(anyhow!(
+            "`{} {}` failed: {}",
+            program,
+            args.first().copied().unwrap_or_default(),
+            stderr.trim()
+        )));
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .with_context(|| format!("`{}` produced non-UTF-8 output", program))?;
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_key() {
+        assert_eq!(normalize_key("API Key"), Some("API_KEY".to_string()));
+        assert_eq!(
+            normalize_key("stripe-secret"),
+            Some("STRIPE_SECRET".to_string())
+        );
+        assert_eq!(
+            normalize_key("  db.password "),
+            Some("DB_PASSWORD".to_string())
+        );
+        assert_eq!(normalize_key("2fa code"), Some("_2FA_CODE".to_string()));
+        assert_eq!(normalize_key("--"), None);
+    }
+
+    #[test]
+    fn test_field_key_prefixes_item_title() {
+        assert_eq!(
+            field_key(None, "password"),
+            Some(("PASSWORD".to_string(), "password".to_string()))
+        );
+        assert_eq!(
+            field_key(Some("Prod DB"), "password"),
+            Some((
+                "PROD_DB_PASSWORD".to_string(),
+                "Prod DB: password".to_string()
+            ))
+        );
+        assert_eq!(
+            field_key(Some("API"), "--"),
+            Some(("API".to_string(), "API: --".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys_rejected() {
+        let entries = vec![
+            ImportedEntry::from(("TOKEN".to_string(), "a".to_string())),
+            ImportedEntry::from(("TOKEN".to_string(), "b".to_string())),
+        ];
+        assert!(ensure_unique_keys(&entries).is_err());
+    }
+}
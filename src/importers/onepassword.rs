@@ -0,0 +1,222 @@
+//! 1Password import source.
+//! Reads items from `op item get --format json` (or a file holding that output) and maps fields to keys.
+
+use super::{ImportOptions, field_key, read_export, run_cli};
+use crate::envfile::ImportedEntry;
+use crate::error::AppError;
+use anyhow::{Context, anyhow};
+use serde::Deserialize;
+
+/// Subset of a 1Password item as emitted by `op item get --format json`.
+#[derive(Deserialize, Debug)]
+struct Item {
+    id: String,
+    title: String,
+    #[serde(default)]
+    vault: Option<VaultRef>,
+    #[serde(default)]
+    fields: Vec<Field>,
+}
+
+#[derive(Deserialize, Debug)]
+struct VaultRef {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Field {
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+}
+
+pub fn load(opts: &ImportOptions) -> Result<Vec<ImportedEntry>, AppError> {
+    let items = match opts.path {
+        Some(path) => parse_items(&read_export(path)?)?,
+        None => fetch_items(opts)?,
+    };
+    let selected: Vec<&Item> = items
+        .iter()
+        .filter(|item| {
+            opts.item
+                .is_none_or(|name| item.title == name || item.id == name)
+        })
+        .filter(|item| {
+            opts.vault.is_none_or(|name| {
+                item.vault.as_ref().and_then(|v| v.name.as_deref()) == Some(name)
+            })
+        })
+        .collect();
+    if selected.is_empty() {
+        return Err(AppError::user(anyhow!("No matching 1Password items found")));
+    }
+    let prefixed = opts.item.is_none();
+    Ok(selected
+        .into_iter()
+        .flat_map(|item| item_entries(item, prefixed))
+        .collect())
+}
+
+/// Query the `op` CLI for one item, or every item in the vault when no item is given.
+fn fetch_items(opts: &ImportOptions) -> Result<Vec<Item>, AppError> {
+    let op = |action: &[&str]| -> Result<Vec<Item>, AppError> {
+        let mut args = vec!["item"];
+        args.extend_from_slice(action);
+        if let Some(vault) = opts.vault {
+            args.extend(["--vault", vault]);
+        }
+        args.extend(["--format", "json"]);
+        parse_items(&run_cli("op", &args)?)
+    };
+    match opts.item {
+        Some(item) => op(&["get", item]),
+        None => {
+            let mut items = Vec::new();
+            for summary in op(&["list"])? {
+                items.extend(op(&["get", summary.id.as_str()])?);
+            }
+            Ok(items)
+        }
+    }
+}
+
+/// Parse one item, a JSON array of items, or a stream of concatenated item objects.
+fn parse_items(content: &str) -> Result<Vec<Item>, AppError> {
+    let mut items = Vec::new();
+    for value in serde_json::Deserializer::from_str(content).into_iter::<serde_json::Value>() {
+        let value = value.context("Failed to parse 1Password JSON")?;
+        if value.is_array() {
+            let batch: Vec<Item> =
+                serde_json::from_value(value).context("Unexpected 1Password item format")?;
+            items.extend(batch);
+        } else {
+            items.push(serde_json::from_value(value).context("Unexpected 1Password item format")?);
+        }
+    }
+    Ok(items)
+}
+
+/// Map each labelled, non-empty field to a vault key; the label becomes the description.
+/// With `prefixed`, keys and descriptions start with the item title.
+fn item_entries(item: &Item, prefixed: bool) -> Vec<ImportedEntry> {
+    let title = prefixed.then_some(item.title.as_str());
+    item.fields
+        .iter()
+        .filter(|f| f.kind.as_deref() != Some("OTP"))
+        .filter_map(|f| {
+            let value = f.value.as_deref().filter(|v| !v.is_empty())?;
+            let (key, description) = field_key(title, f.label.as_deref()?)?;
+            Some(ImportedEntry {
+                key,
+                value: value.to_string(),
+                description: Some(description),
+                environment: None,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEM: &str = r#"{
+        "id": "abc123",
+        "title": "API Keys",
+        "vault": {"id": "v1", "name": "Dev"},
+        "fields": [
+            {"id": "username", "type": "STRING", "purpose": "USERNAME", "label": "username", "value": "deploy"},
+            {"id": "notesPlain", "type": "STRING", "purpose": "NOTES", "label": "notesPlain", "value": ""},
+            {"id": "f1", "type": "CONCEALED", "label": "Stripe Secret Key", "value": "sk_live_1"},
+            {"id": "f2", "type": "OTP", "label": "one-time password", "value": "otpauth://x"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_fields_map_to_keys_with_label_descriptions() {
+        let items = parse_items(ITEM).unwrap();
+        let entries = item_entries(&items[0], false);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].key, "USERNAME");
+        assert_eq!(entries[1].key, "STRIPE_SECRET_KEY");
+        assert_eq!(entries[1].value, "sk_live_1");
+        assert_eq!(entries[1].description.as_deref(), Some("Stripe Secret Key"));
+    }
+
+    #[test]
+    fn test_export_file_filters_by_item_and_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("op.json");
+        let other = ITEM
+            .replace("API Keys", "Other")
+            .replace("abc123", "def456");
+        std::fs::write(&path, format!("{}\n{}", ITEM, other)).unwrap();
+
+        let opts = ImportOptions {
+            path: Some(&path),
+            vault: Some("Dev"),
+            item: Some("API Keys"),
+            ..Default::default()
+        };
+        assert_eq!(load(&opts).unwrap().len(), 2);
+
+        let opts = ImportOptions {
+            path: Some(&path),
+            vault: Some("Prod"),
+            item: None,
+            ..Default::default()
+        };
+        assert!(load(&opts).is_err());
+    }
+
+    #[test]
+    fn test_vault_filter_skips_items_without_a_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("op.json");
+        let loose = ITEM.replace(r#""vault": {"id": "v1", "name": "Dev"},"#, "");
+        std::fs::write(&path, loose).unwrap();
+
+        let opts = ImportOptions {
+            path: Some(&path),
+            vault: Some("Dev"),
+            ..Default::default()
+        };
+        assert!(load(&opts).is_err());
+    }
+
+    #[test]
+    fn test_whole_vault_import_prefixes_item_titles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("op.json");
+        let other = ITEM
+            .replace("API Keys", "Other")
+            .replace("abc123", "def456");
+        std::fs::write(&path, format!("[{},{}]", ITEM, other)).unwrap();
+
+        let opts = ImportOptions {
+            path: Some(&path),
+            vault: Some("Dev"),
+            ..Default::default()
+        };
+        let entries = load(&opts).unwrap();
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "API_KEYS_USERNAME",
+                "API_KEYS_STRIPE_SECRET_KEY",
+                "OTHER_USERNAME",
+                "OTHER_STRIPE_SECRET_KEY"
+            ]
+        );
+        assert_eq!(
+            entries[1].description.as_deref(),
+            Some("API Keys: Stripe Secret Key")
+        );
+    }
+}
@@ -14,9 +14,7 @@ pub fn require_yes(flags: &CliFlags, action: &str) -> Result<(), AppError> {
 }
 
 pub fn print_out(flags: &CliFlags, msg: &str) {
-    if !flags.json && !flags.no_color {
-        println!("{}", msg);
-    } else if !flags.json {
+    if !flags.json {
         println!("{}", msg);
     }
 }
@@ -34,9 +32,7 @@ pub fn print_json(payload: &serde_json::Value) {
 }
 
 pub fn print_err(flags: &CliFlags, msg: &str) {
-    if !flags.json && !flags.no_color {
-        eprintln!("{}", msg);
-    } else if !flags.json {
+    if !flags.json {
         eprintln!("{}", msg);
     }
 }
@@ -65,3 +61,53 @@ pub fn read_token_securely(
         }
     }
 }
+
+/// Where `secret set` reads its value from; argv is the last resort since it leaks into
+/// shell history and `ps`.
+pub enum ValueSource<'a> {
+    Arg(String),
+    Stdin,
+    File(&'a str),
+    Prompt,
+}
+
+/// Read a secret value from its source. Stdin drops one trailing newline (as `echo`
+/// adds); files are kept verbatim so PEM and JSON keys round-trip exactly.
+pub fn read_secret_value(
+    key: &str,
+    source: ValueSource<'_>,
+    flags: &CliFlags,
+) -> Result<String, AppError> {
+    let value = match source {
+        ValueSource::Arg(value) => value,
+        ValueSource::Stdin => {
+            let mut value = std::io::read_to_string(std::io::stdin())
+                .context("Failed to read value from stdin")
+                .map_err(AppError::user)?;
+            if value.ends_with('\n') {
+                value.pop();
+                if value.ends_with('\r') {
+                    value.pop();
+                }
+            }
+            value
+        }
+        ValueSource::File(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path))
+            .map_err(AppError::user)?,
+        ValueSource::Prompt => {
+            if flags.non_interactive {
+                return Err(AppError::user(anyhow::anyhow!(
+                    "--non-interactive set; provide the value via --stdin or --from-file"
+                )));
+            }
+            prompt_password(format!("Enter value for {}: ", key))
+                .context("Failed to read value securely")
+                .map_err(AppError::user)?
+        }
+    };
+    if value.is_empty() {
+        return Err(AppError::user(anyhow::anyhow!("Value cannot be empty")));
+    }
+    Ok(value)
+}
@@ -0,0 +1,302 @@
+//! Passphrase-wrapped master key file (`.cred/master.key`).
+//!
+//! An alternative to the OS keyring for headless machines: the 32-byte project key is
+//! encrypted with ChaCha20-Poly1305 under a key derived from a passphrase with Argon2id.
+//! The KDF parameters and salt are stored alongside the ciphertext so they can be raised
+//! later without breaking existing files.
+
+use anyhow::{Context, Result, anyhow, bail};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chacha20poly1305::{
+    ChaCha20Poly1305, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use rand::RngCore;
+use rpassword::prompt_password;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use zeroize::Zeroize;
+
+use crate::fsutil;
+
+/// File name of the wrapped key under `.cred/`.
+pub const KEY_FILE_NAME: &str = "master.key";
+
+/// Env var consulted before prompting for the passphrase (CI, scripts).
+pub const PASSPHRASE_ENV: &str = "CRED_PASSPHRASE";
+
+const KEY_FILE_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+
+/// Upper bounds on Argon2id costs read from key files, pasted blobs and bundles, so a
+/// crafted header cannot make `open` allocate or spin without limit.
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 16;
+const MAX_PARALLELISM: u32 = 16;
+
+/// Cleared by `--non-interactive`; passphrase prompts fail instead of blocking.
+static PROMPTS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Allow or forbid interactive passphrase prompts for the rest of the process.
+pub fn set_prompts_enabled(enabled: bool) {
+    PROMPTS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Argon2id cost parameters.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+#[cfg(test)]
+impl KdfParams {
+    /// Minimal cost parameters so tests don't pay for a real Argon2id derivation.
+    pub fn fast() -> Self {
+        KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Reject costs above `MAX_MEMORY_KIB`, `MAX_ITERATIONS` or `MAX_PARALLELISM`.
+    fn check_bounds(&self) -> Result<()> {
+        if self.memory_kib > MAX_MEMORY_KIB
+            || self.iterations > MAX_ITERATIONS
+            || self.parallelism > MAX_PARALLELISM
+        {
+            bail!(
+                "Wrapped key asks for excessive KDF costs ({} KiB, {} iterations, {} lanes)",
+                self.memory_kib,
+                self.iterations,
+                self.parallelism
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for KdfParams {
+    /// 64 MiB, 3 passes, single lane.
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct KdfSection {
+    algorithm: String,
+    #[serde(flatten)]
+    params: KdfParams,
+    salt: String,
+}
+
+/// On-disk representation of a passphrase-wrapped key.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WrappedKey {
+    version: u8,
+    kdf: KdfSection,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl WrappedKey {
+    /// Encrypt `key` under `passphrase` with a fresh salt and nonce.
+    pub fn seal(key: &[u8; 32], passphrase: &str, params: KdfParams) -> Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        rand::rng().fill_bytes(&mut salt);
+        let mut kek = derive_key(passphrase, &salt, params)?;
+
+        let cipher = ChaCha20Poly1305::new(&kek.into());
+        kek.zeroize();
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, key.as_ref())
+            .map_err(|e| anyhow!("Encryption failed: {:?}", e))?;
+
+        Ok(WrappedKey {
+            version: KEY_FILE_VERSION,
+            kdf: KdfSection {
+                algorithm: "argon2id".to_string(),
+                params,
+                salt: BASE64.encode(salt),
+            },
+            cipher: "chacha20poly1305".to_string(),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    /// Decrypt the wrapped key. A wrong passphrase surfaces as a decryption error.
+    pub fn open(&self, passphrase: &str) -> Result<[u8; 32]> {
+        if self.version != KEY_FILE_VERSION {
+            bail!(
+                "Unsupported key file version: {}. Please upgrade cred.",
+                self.version
+            );
+        }
+        if self.kdf.algorithm != "argon2id" || self.cipher != "chacha20poly1305" {
+            bail!(
+                "Unsupported key file algorithms: {}/{}",
+                self.kdf.algorithm,
+                self.cipher
+            );
+        }
+        self.kdf.params.check_bounds()?;
+        let salt = BASE64
+            .decode(&self.kdf.salt)
+            .context("Invalid salt base64")?;
+        let nonce_bytes = BASE64.decode(&self.nonce).context("Invalid nonce base64")?;
+        let ciphertext = BASE64
+            .decode(&self.ciphertext)
+            .context("Invalid ciphertext base64")?;
+        if nonce_bytes.len() != 12 {
+            bail!("Invalid nonce length in key file");
+        }
+
+        let mut kek = derive_key(passphrase, &salt, self.kdf.params)?;
+        let cipher = ChaCha20Poly1305::new(&kek.into());
+        kek.zeroize();
+        let mut plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce_bytes), ciphertext.as_ref())
+            .map_err(|_| anyhow!("Wrong passphrase or corrupted key file"))?;
+
+        if plaintext.len() != 32 {
+            plaintext.zeroize();
+            bail!("Invalid key length in key file");
+        }
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&plaintext);
+        plaintext.zeroize();
+        Ok(key)
+    }
+
+    /// KDF parameters this key was wrapped with (reused when re-wrapping a rotated key).
+    pub fn params(&self) -> KdfParams {
+        self.kdf.params
+    }
+
+    /// Compact binary form for paper backups:
+    /// `memory_kib | iterations | parallelism` (u32 BE) `| salt | nonce | ciphertext`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.kdf.params.memory_kib.to_be_bytes());
+        out.extend_from_slice(&self.kdf.params.iterations.to_be_bytes());
+        out.extend_from_slice(&self.kdf.params.parallelism.to_be_bytes());
+        out.extend(
+            BASE64
+                .decode(&self.kdf.salt)
+                .context("Invalid salt base64")?,
+        );
+        out.extend(BASE64.decode(&self.nonce).context("Invalid nonce base64")?);
+        out.extend(
+            BASE64
+                .decode(&self.ciphertext)
+                .context("Invalid ciphertext base64")?,
+        );
+        Ok(out)
+    }
+
+    /// Inverse of `to_bytes` (Argon2id + ChaCha20-Poly1305 are implied).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        const HEADER: usize = 12 + SALT_LEN + 12;
+        if bytes.len() <= HEADER {
+            bail!("Wrapped key is truncated");
+        }
+        let u32_at =
+            |i: usize| u32::from_be_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let params = KdfParams {
+            memory_kib: u32_at(0),
+            iterations: u32_at(4),
+            parallelism: u32_at(8),
+        };
+        params.check_bounds()?;
+        Ok(WrappedKey {
+            version: KEY_FILE_VERSION,
+            kdf: KdfSection {
+                algorithm: "argon2id".to_string(),
+                params,
+                salt: BASE64.encode(&bytes[12..12 + SALT_LEN]),
+            },
+            cipher: "chacha20poly1305".to_string(),
+            nonce: BASE64.encode(&bytes[12 + SALT_LEN..HEADER]),
+            ciphertext: BASE64.encode(&bytes[HEADER..]),
+        })
+    }
+
+    /// Read a wrapped key from disk.
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse key file")
+    }
+
+    /// Write the wrapped key to disk as pretty JSON.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fsutil::write_atomic(path, json.as_bytes())
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], params: KdfParams) -> Result<[u8; 32]> {
+    let params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(32),
+    )
+    .map_err(|e| anyhow!("Invalid KDF parameters: {}", e))?;
+    let argon = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    let mut out = [0u8; 32];
+    argon
+        .hash_password_into(passphrase.as_bytes(), salt, &mut out)
+        .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    Ok(out)
+}
+
+/// Obtain a passphrase from `CRED_PASSPHRASE` or an interactive prompt.
+/// With `confirm`, the prompt asks twice (used when setting a new passphrase).
+pub fn read_passphrase(prompt: &str, confirm: bool) -> Result<String> {
+    read_passphrase_from(PASSPHRASE_ENV, prompt, confirm)
+}
+
+/// Like `read_passphrase`, but consulting `env_var` before prompting.
+pub fn read_passphrase_from(env_var: &str, prompt: &str, confirm: bool) -> Result<String> {
+    if let Ok(p) = std::env::var(env_var)
+        && !p.is_empty()
+    {
+        return Ok(p);
+    }
+    if !PROMPTS_ENABLED.load(Ordering::Relaxed) {
+        bail!(
+            "--non-interactive set; provide the passphrase via {}",
+            env_var
+        );
+    }
+    let passphrase = prompt_password(prompt).context("Failed to read passphrase")?;
+    if passphrase.is_empty() {
+        bail!("Passphrase cannot be empty");
+    }
+    if confirm {
+        let mut again =
+            prompt_password("Confirm passphrase: ").context("Failed to read passphrase")?;
+        let matches = again == passphrase;
+        again.zeroize();
+        if !matches {
+            bail!("Passphrases do not match");
+        }
+    }
+    Ok(passphrase)
+}
@@ -1,12 +1,27 @@
 //! cred CLI entrypoint and command dispatcher.
 //! Parses args, routes to subcommands, and handles uniform error/exit code reporting.
+mod bundle;
+mod cert;
 mod cli;
+mod commands;
 mod config;
 mod envfile;
 mod error;
+mod expiry;
+mod formats;
+mod fsutil;
+mod generate;
+mod importers;
 mod io;
+mod keyfile;
 mod project;
+mod recovery;
+mod render;
+mod rotation;
+mod runner;
+mod shell;
 mod targets;
+mod team;
 #[cfg(test)]
 mod tests;
 mod vault;
@@ -14,7 +29,10 @@ mod vault;
 use clap::Parser;
 use cli::{Cli, CliFlags, Commands, SecretAction, SetTargetArgs};
 use error::{AppError, ExitCode};
-use io::{print_err, print_json, print_out, print_plain_err, read_token_securely, require_yes};
+use io::{
+    ValueSource, print_err, print_json, print_out, print_plain_err, read_secret_value,
+    read_token_securely, require_yes,
+};
 use keyring::Entry;
 use project::{ProjectStatusData, resolve_repo_binding};
 use std::process;
@@ -25,13 +43,11 @@ use zeroize::Zeroize;
 /// Tokio runtime entrypoint; parses CLI and normalizes exit codes/JSON errors.
 async fn main() {
     let cli = Cli::parse();
-    let no_color_env = std::env::var("NO_COLOR").is_ok();
     let flags = CliFlags {
         json: cli.json,
         non_interactive: cli.non_interactive,
         dry_run: cli.dry_run,
         yes: cli.yes,
-        no_color: no_color_env || cli.json,
     };
     match run(cli, &flags).await {
         Ok(()) => process::exit(ExitCode::Ok as i32),
@@ -64,10 +80,22 @@ async fn main() {
 
 /// Core dispatcher for all subcommands.
 async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
+    keyfile::set_prompts_enabled(!flags.non_interactive);
+    let env = cli.env.clone();
+    let env = env.as_deref();
     match cli.command {
-        Commands::Init => {
+        Commands::Init { passphrase } => {
             config::ensure_global_config_exists()?;
-            project::init()?;
+            if passphrase {
+                let mut p = keyfile::read_passphrase("New vault passphrase: ", true)
+                    .map_err(AppError::user)?;
+                let root = std::env::current_dir().map_err(|e| AppError::user(e.into()))?;
+                let res = project::init_at_with(&root, Some(&p));
+                p.zeroize();
+                res?;
+            } else {
+                project::init()?;
+            }
             if flags.json {
                 let payload = serde_json::json!({
                     "api_version": "1",
@@ -84,7 +112,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     print_out(flags, "(dry-run) Target set skipped");
                     return Ok(());
                 }
-                handle_target_set(args, &flags)?;
+                handle_target_set(args, flags)?;
             }
             cli::TargetAction::List => {
                 let cfg = config::load()?;
@@ -105,7 +133,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                 }
             }
             cli::TargetAction::Revoke { name } => {
-                require_yes(&flags, "target revoke")?;
+                require_yes(flags, "target revoke")?;
                 if flags.dry_run {
                     print_out(flags, "(dry-run) Target revoke skipped");
                     return Ok(());
@@ -131,25 +159,145 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
 
         Commands::Secret { action } => {
             let proj = project::Project::find()?;
-            let master_key = proj.get_master_key()?;
-            let mut vault = vault::Vault::load(&proj.vault_path, master_key)?;
+            let mut vault = proj.open_vault(env)?;
 
             match action {
                 SecretAction::Set {
                     key,
                     value,
+                    stdin,
+                    from_file,
                     description,
                     format,
+                    scopes,
+                    expires,
+                    ttl,
                 } => {
+                    project::validate_scope_names(&scopes)?;
+                    let expires_at =
+                        expiry::parse(expires.as_deref(), ttl.as_deref(), chrono::Utc::now())?;
                     if flags.dry_run {
                         println!("(dry-run) Would set {}", key);
                         return Ok(());
                     }
+                    let source = match (value, stdin, from_file.as_deref()) {
+                        (Some(value), _, _) => ValueSource::Arg(value),
+                        (None, true, _) => ValueSource::Stdin,
+                        (None, false, Some(path)) => ValueSource::File(path),
+                        (None, false, None) => ValueSource::Prompt,
+                    };
+                    let mut value = read_secret_value(&key, source, flags)?;
                     // Use explicit format if provided, otherwise auto-detect
                     let fmt = format.unwrap_or_else(|| vault::Vault::detect_format(&value));
                     vault.set_with_metadata(&key, &value, fmt, description);
+                    if expires_at.is_some() {
+                        vault.set_expiry(&key, expires_at);
+                    }
+                    let saved = vault.save();
+                    value.zeroize();
+                    saved?;
+                    proj.add_key_to_scopes(&scopes, &key)?;
+                    if scopes.is_empty() {
+                        print_out(flags, &format!("✓ Set {} = *****", key));
+                    } else {
+                        print_out(
+                            flags,
+                            &format!("✓ Set {} = ***** (scopes: {})", key, scopes.join(", ")),
+                        );
+                    }
+                    if let Some(at) = expires_at {
+                        print_out(
+                            flags,
+                            &format!("  Expires {}", at.format("%Y-%m-%d %H:%M UTC")),
+                        );
+                    }
+                }
+                SecretAction::Generate {
+                    key,
+                    kind,
+                    length,
+                    charset,
+                    description,
+                    scopes,
+                    rotate_every,
+                    force,
+                } => {
+                    project::validate_scope_names(&scopes)?;
+                    let recipe = generate::Recipe::new(kind, length, charset)?;
+                    let rotation = rotate_every
+                        .as_deref()
+                        .map(rotation::parse_days)
+                        .transpose()
+                        .map_err(AppError::user)?
+                        .map(|max_age_days| rotation::RotationPolicy {
+                            max_age_days,
+                            recipe: recipe.clone(),
+                        });
+                    let public_key = format!("{}{}", key, generate::PUBLIC_KEY_SUFFIX);
+                    if !force && vault.get(&key).is_some() {
+                        return Err(AppError::user(anyhow::anyhow!(
+                            "Secret '{}' exists; rerun with --force to replace it (the old value is kept in history)",
+                            key
+                        )));
+                    }
+                    if flags.dry_run {
+                        print_out(
+                            flags,
+                            &format!("(dry-run) Would set {} to a {}", key, recipe.describe()),
+                        );
+                        return Ok(());
+                    }
+                    let generated = recipe.generate()?;
+                    let description = description.unwrap_or_else(|| recipe.describe());
+                    vault.set_with_metadata(
+                        &key,
+                        &generated.value,
+                        generated.format.clone(),
+                        Some(description.clone()),
+                    );
+                    vault.set_rotation(&key, rotation.clone());
+                    if let Some(public) = &generated.public {
+                        vault.set_with_metadata(
+                            &public_key,
+                            public,
+                            vault::Vault::detect_format(public),
+                            Some(format!("Public key for {}", key)),
+                        );
+                    }
                     vault.save()?;
-                    print_out(flags, &format!("✓ Set {} = *****", key));
+                    proj.add_key_to_scopes(&scopes, &key)?;
+
+                    if flags.json {
+                        let payload = serde_json::json!({
+                            "api_version": "1",
+                            "status": "ok",
+                            "data": {
+                                "key": key,
+                                "recipe": recipe,
+                                "format": generated.format.to_string(),
+                                "description": description,
+                                "public_key": generated.public.as_ref().map(|_| &public_key),
+                                "rotation": rotation,
+                            }
+                        });
+                        print_json(&payload);
+                    } else {
+                        print_out(flags, &format!("✓ Set {} = ***** ({})", key, description));
+                        if let Some(policy) = &rotation {
+                            print_out(
+                                flags,
+                                &format!(
+                                    "  Rotates every {} days (next due {})",
+                                    policy.max_age_days,
+                                    policy.due_at(chrono::Utc::now()).format("%Y-%m-%d")
+                                ),
+                            );
+                        }
+                        if let Some(public) = &generated.public {
+                            print_out(flags, &format!("✓ Stored public key as {}:", public_key));
+                            print_out(flags, public.trim_end());
+                        }
+                    }
                 }
                 SecretAction::Get { key } => match vault.get_entry(&key) {
                     Some(entry) => {
@@ -164,6 +312,12 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                                     "created_at": entry.created_at.to_rfc3339(),
                                     "updated_at": entry.updated_at.to_rfc3339(),
                                     "description": entry.description,
+                                    "environment": entry.environment,
+                                    "expires_at": entry.expires_at.map(|t| t.to_rfc3339()),
+                                    "pem": (entry.format == vault::SecretFormat::Pem)
+                                        .then(|| cert::inspect(&entry.value).ok())
+                                        .flatten()
+                                        .filter(|info| !info.is_empty()),
                                 }
                             });
                             println!("{}", serde_json::to_string(&payload).unwrap_or_default());
@@ -174,7 +328,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     None => print_err(flags, &format!("Secret '{}' not found", key)),
                 },
                 SecretAction::Remove { key } => {
-                    require_yes(&flags, "secret remove")?;
+                    require_yes(flags, "secret remove")?;
                     if flags.dry_run {
                         if let Some(entry) = vault.get_entry(&key) {
                             if flags.json {
@@ -206,8 +360,15 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         }
                         return Ok(());
                     }
+                    if vault.is_inherited(&key) {
+                        return Err(AppError::user(anyhow::anyhow!(
+                            "'{}' is inherited; remove it from the environment that defines it",
+                            key
+                        )));
+                    }
                     if let Some(entry) = vault.remove_entry(&key) {
                         vault.save()?;
+                        proj.remove_key_from_scopes(&key)?;
                         if flags.json {
                             let payload = serde_json::json!({
                                 "api_version": "1",
@@ -240,22 +401,43 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         print_out(flags, &format!("Secret '{}' did not exist locally.", key));
                     }
                 }
-                SecretAction::List {} => {
-                    let entries = vault.list_entries();
-                    let mut keys: Vec<&String> = entries.keys().collect();
+                SecretAction::List { scope } => {
+                    let entries = vault.effective_entries();
+                    let scoped = match scope.as_deref() {
+                        Some(s) => Some(project::scope_keys(&proj.load_config()?, s)?),
+                        None => None,
+                    };
+                    let mut keys: Vec<&String> = entries
+                        .keys()
+                        .copied()
+                        .filter(|k| scoped.as_ref().is_none_or(|ks| ks.contains(k)))
+                        .collect();
                     keys.sort();
+                    let now = chrono::Utc::now();
                     if flags.json {
                         let secrets_data: Vec<serde_json::Value> = keys
                             .iter()
                             .map(|k| {
-                                let entry = &entries[*k];
+                                let entry = entries[*k];
                                 serde_json::json!({
                                     "key": k,
                                     "format": entry.format.to_string(),
                                     "created_at": entry.created_at.to_rfc3339(),
                                     "updated_at": entry.updated_at.to_rfc3339(),
                                     "description": entry.description,
+                                    "environment": entry.environment,
                                     "modified": vault.is_dirty(k),
+                                    "inherited": vault.is_inherited(k),
+                                    "rotation_due_at": entry
+                                        .rotation
+                                        .as_ref()
+                                        .map(|p| p.due_at(entry.updated_at).to_rfc3339()),
+                                    "rotation_overdue": entry.rotation_due(now),
+                                    "expires_at": entry.expires_at.map(|t| t.to_rfc3339()),
+                                    "expired": entry.expiry_status(now)
+                                        == Some(expiry::Status::Expired),
+                                    "expiring_soon": entry.expiry_status(now)
+                                        == Some(expiry::Status::ExpiringSoon),
                                 })
                             })
                             .collect();
@@ -268,13 +450,44 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     } else {
                         println!("Vault content:");
                         for k in keys {
-                            let entry = &entries[k];
+                            let entry = entries[k];
                             let modified_marker =
                                 if vault.is_dirty(k) { " [modified]" } else { "" };
+                            let inherited_marker = if vault.is_inherited(k) {
+                                " [inherited]"
+                            } else {
+                                ""
+                            };
+                            let rotation_marker = if entry.rotation_due(now) {
+                                " [rotation overdue]"
+                            } else {
+                                ""
+                            };
+                            let expiry_marker = match entry.expires_at {
+                                Some(at) if entry.expiry_status(now).is_some() => {
+                                    format!(" [{}]", expiry::describe(at, now))
+                                }
+                                _ => String::new(),
+                            };
                             if let Some(desc) = &entry.description {
-                                println!("  {} = ***** ({}){}", k, desc, modified_marker);
+                                println!(
+                                    "  {} = ***** ({}){}{}{}{}",
+                                    k,
+                                    desc,
+                                    modified_marker,
+                                    inherited_marker,
+                                    rotation_marker,
+                                    expiry_marker
+                                );
                             } else {
-                                println!("  {} = *****{}", k, modified_marker);
+                                println!(
+                                    "  {} = *****{}{}{}{}",
+                                    k,
+                                    modified_marker,
+                                    inherited_marker,
+                                    rotation_marker,
+                                    expiry_marker
+                                );
                             }
                         }
                     }
@@ -293,6 +506,12 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         }
                         return Ok(());
                     }
+                    if vault.is_inherited(&key) {
+                        return Err(AppError::user(anyhow::anyhow!(
+                            "'{}' is inherited; describe it in the environment that defines it",
+                            key
+                        )));
+                    }
                     if vault.set_description(&key, description.clone()) {
                         vault.save()?;
                         match &description {
@@ -307,8 +526,99 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         print_err(flags, &format!("Secret '{}' not found", key));
                     }
                 }
+                SecretAction::Expire { key, expires, ttl } => {
+                    let expires_at =
+                        expiry::parse(expires.as_deref(), ttl.as_deref(), chrono::Utc::now())?;
+                    if vault.is_inherited(&key) {
+                        return Err(AppError::user(anyhow::anyhow!(
+                            "'{}' is inherited; set its expiry in the environment that defines it",
+                            key
+                        )));
+                    }
+                    if vault.get_entry(&key).is_none() {
+                        return Err(AppError::user(anyhow::anyhow!(
+                            "Secret '{}' not found",
+                            key
+                        )));
+                    }
+                    let when = expires_at.map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string());
+                    if flags.dry_run {
+                        match &when {
+                            Some(w) => print_out(
+                                flags,
+                                &format!("(dry-run) Would set '{}' to expire {}", key, w),
+                            ),
+                            None => print_out(
+                                flags,
+                                &format!("(dry-run) Would clear the expiry of '{}'", key),
+                            ),
+                        }
+                        return Ok(());
+                    }
+                    vault.set_expiry(&key, expires_at);
+                    vault.save()?;
+                    match &when {
+                        Some(w) => print_out(flags, &format!("✓ '{}' expires {}", key, w)),
+                        None => print_out(flags, &format!("✓ Cleared the expiry of '{}'", key)),
+                    }
+                }
+                SecretAction::Inspect { key, pair } => {
+                    commands::secret_inspect::handle(&vault, &key, pair.as_deref(), flags)?;
+                }
+                SecretAction::History { key, show, diff } => {
+                    commands::secret_history::handle(&vault, &key, show, diff, flags)?;
+                }
+                SecretAction::Rollback { key, version } => {
+                    if vault.is_inherited(&key) {
+                        return Err(AppError::user(anyhow::anyhow!(
+                            "'{}' is inherited; roll it back in the environment that defines it",
+                            key
+                        )));
+                    }
+                    if flags.dry_run {
+                        let exists = vault
+                            .get_entry(&key)
+                            .and_then(|e| e.history_version(version))
+                            .is_some();
+                        if !exists {
+                            return Err(AppError::user(anyhow::anyhow!(
+                                "Version {} of '{}' not found in history",
+                                version,
+                                key
+                            )));
+                        }
+                        print_out(
+                            flags,
+                            &format!("(dry-run) Would roll back '{}' to version {}", key, version),
+                        );
+                        return Ok(());
+                    }
+                    let new_version = vault.rollback(&key, version)?;
+                    vault.save()?;
+                    if flags.json {
+                        let payload = serde_json::json!({
+                            "api_version": "1",
+                            "status": "ok",
+                            "data": {
+                                "action": "rollback",
+                                "key": key,
+                                "restored": version,
+                                "version": new_version,
+                            }
+                        });
+                        print_json(&payload);
+                    } else {
+                        print_out(
+                            flags,
+                            &format!(
+                                "✓ Rolled back '{}' to version {} (now version {})",
+                                key, version, new_version
+                            ),
+                        );
+                    }
+                }
                 SecretAction::Revoke { key, target } => {
-                    require_yes(&flags, "secret revoke")?;
+                    require_yes(flags, "secret revoke")?;
                     if flags.dry_run {
                         print_out(
                             flags,
@@ -369,11 +679,26 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
 
         Commands::Import(args) => {
             let proj = project::Project::find()?;
-            let master_key = proj.get_master_key()?;
-            let mut vault = vault::Vault::load(&proj.vault_path, master_key)?;
+            let mut vault = proj.open_vault(env)?;
 
-            let path = std::path::Path::new(&args.path);
-            let entries = envfile::parse_env_file(path)?;
+            let mut dotenv_key = if args.dotenv_key_stdin {
+                Some(commands::read_input("-")?.trim().to_string())
+            } else {
+                None
+            };
+            let opts = importers::ImportOptions {
+                path: args.path.as_deref().map(std::path::Path::new),
+                vault: args.vault.as_deref(),
+                item: args.item.as_deref(),
+                environment: args.environment.as_deref(),
+                dotenv_key: dotenv_key.as_deref(),
+                expand: args.expand,
+                format: args.format,
+                separator: Some(&args.separator),
+            };
+            let entries = importers::load(args.from, &opts);
+            dotenv_key.zeroize();
+            let entries = entries?;
             let stats =
                 envfile::import_entries(&entries, &mut vault, args.overwrite, flags.dry_run);
 
@@ -381,12 +706,14 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                 vault.save()?;
             }
 
+            let source = args.path.clone().unwrap_or_else(|| args.from.to_string());
             if flags.json {
                 let payload = serde_json::json!({
                     "api_version": "1",
                     "status": "ok",
                     "data": {
                         "path": args.path,
+                        "source": args.from.to_string(),
                         "added": stats.added,
                         "overwritten": stats.overwritten,
                         "skipped": stats.skipped,
@@ -399,7 +726,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     flags,
                     &format!(
                         "(dry-run) Would import from {} (add {}, overwrite {}, skip {}).",
-                        args.path, stats.added, stats.overwritten, stats.skipped
+                        source, stats.added, stats.overwritten, stats.skipped
                     ),
                 );
             } else {
@@ -407,7 +734,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     flags,
                     &format!(
                         "✓ Imported {} (added {}, overwritten {}, skipped {}).",
-                        args.path, stats.added, stats.overwritten, stats.skipped
+                        source, stats.added, stats.overwritten, stats.skipped
                     ),
                 );
             }
@@ -415,11 +742,25 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
 
         Commands::Export(args) => {
             let proj = project::Project::find()?;
-            let master_key = proj.get_master_key()?;
-            let vault = vault::Vault::load(&proj.vault_path, master_key)?;
+            let vault = proj.open_vault(env)?;
 
+            let scoped = match args.scope.as_deref() {
+                Some(s) => Some(project::scope_keys(&proj.load_config()?, s)?),
+                None => None,
+            };
             let path = std::path::Path::new(&args.path);
-            let count = envfile::export_env_file(&vault, path, args.force, flags.dry_run)?;
+            let format = args
+                .format
+                .unwrap_or_else(|| formats::FileFormat::detect(path));
+            let count = formats::export_file(
+                &vault,
+                path,
+                scoped.as_deref(),
+                format,
+                args.metadata,
+                args.force,
+                flags.dry_run,
+            )?;
 
             if flags.json {
                 let payload = serde_json::json!({
@@ -427,6 +768,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     "status": "ok",
                     "data": {
                         "path": args.path,
+                        "format": format.to_string(),
                         "exported": count,
                         "dry_run": flags.dry_run
                     }
@@ -445,6 +787,142 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
             }
         }
 
+        Commands::Run(args) => {
+            let proj = project::Project::find()?;
+            let keys = commands::selected_keys(&proj, args.only, args.scope.as_deref())?;
+            // Collect, then drop the vault so its lock isn't held while the command runs.
+            let run_env = {
+                let vault = proj.open_vault(env)?;
+                runner::collect_env(&vault, keys.as_deref(), args.files && !flags.dry_run)?
+            };
+            if flags.dry_run {
+                print_out(
+                    flags,
+                    &format!(
+                        "(dry-run) Would run `{}` with {} secrets: {}",
+                        args.command.join(" "),
+                        run_env.names().len(),
+                        run_env.names().join(", ")
+                    ),
+                );
+                return Ok(());
+            }
+            let code = runner::run_command(&args.command, &run_env).await?;
+            drop(run_env);
+            process::exit(code);
+        }
+
+        Commands::Env(args) => {
+            let proj = project::Project::find()?;
+            let keys = commands::selected_keys(&proj, args.only, args.scope.as_deref())?;
+            let shell = args.shell.unwrap_or_else(shell::Shell::detect);
+            let vault = proj.open_vault(env)?;
+            let mut script = String::new();
+            let selected = vault.select_entries(keys.as_deref())?;
+            for (key, entry) in &selected {
+                script.push_str(&shell.export(key, &entry.value)?);
+                script.push('\n');
+            }
+            if flags.json {
+                let payload = serde_json::json!({
+                    "api_version": "1",
+                    "status": "ok",
+                    "data": {
+                        "shell": format!("{:?}", shell).to_lowercase(),
+                        "count": selected.len(),
+                        "script": script,
+                    }
+                });
+                print_json(&payload);
+            } else {
+                print!("{}", script);
+            }
+            script.zeroize();
+        }
+
+        Commands::Render(args) => {
+            let proj = project::Project::find()?;
+            let template = commands::read_input(&args.template)?;
+            let vault = proj.open_vault(env)?;
+            let entries: std::collections::HashMap<&str, &str> = vault
+                .effective_entries()
+                .into_iter()
+                .map(|(k, e)| (k.as_str(), e.value.as_str()))
+                .collect();
+            let mut rendered =
+                render::render(&template, |k| entries.get(k).copied()).map_err(AppError::user)?;
+
+            match args.output.as_deref() {
+                None => {
+                    if !flags.dry_run {
+                        print!("{}", rendered.text);
+                    }
+                }
+                Some(path) => {
+                    let out = std::path::Path::new(path);
+                    if out.exists() && !args.force {
+                        return Err(AppError::user(anyhow::anyhow!(
+                            "{} already exists; use --force to overwrite",
+                            path
+                        )));
+                    }
+                    if flags.dry_run {
+                        print_out(
+                            flags,
+                            &format!(
+                                "(dry-run) Would render {} secrets into {}",
+                                rendered.keys.len(),
+                                path
+                            ),
+                        );
+                    } else {
+                        fsutil::write_atomic(out, rendered.text.as_bytes())?;
+                        print_out(
+                            flags,
+                            &format!("✓ Rendered {} secrets into {}", rendered.keys.len(), path),
+                        );
+                    }
+                    if flags.json {
+                        let payload = serde_json::json!({
+                            "api_version": "1",
+                            "status": "ok",
+                            "data": {
+                                "output": path,
+                                "keys": rendered.keys,
+                                "dry_run": flags.dry_run,
+                            }
+                        });
+                        print_json(&payload);
+                    }
+                }
+            }
+            rendered.text.zeroize();
+        }
+
+        Commands::Direnv { stdlib } => {
+            if stdlib {
+                print!("{}", shell::DIRENV_STDLIB);
+                return Ok(());
+            }
+            let proj = project::Project::find()?;
+            let config = proj.load_config()?;
+            let mut script = String::new();
+            let chain = project::resolve_environment_chain(&config, env)?;
+            let watched = std::iter::once(proj.config_path.clone())
+                .chain(chain.iter().map(|e| proj.env_vault_path(e)));
+            for path in watched {
+                script.push_str(&shell::direnv_watch(&path));
+                script.push('\n');
+            }
+            let vault = proj.open_vault(env)?;
+            for (key, entry) in vault.select_entries(None)? {
+                script.push_str(&shell::Shell::Bash.export(key, &entry.value)?);
+                script.push('\n');
+            }
+            print!("{}", script);
+            script.zeroize();
+        }
+
         Commands::Push(args) => {
             let target_impl = match targets::get(args.target) {
                 Some(p) => p,
@@ -457,31 +935,21 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                 }
             };
 
-            let token = config::get_target_token(&args.target.to_string())?
-                .ok_or_else(|| anyhow::anyhow!("No token found for {}.", args.target))?;
-
             let proj = project::Project::find()?;
-            let git_info = project::detect_git(None);
-            let bound_repo = proj.load_config().ok().and_then(|c| c.git_repo);
-
-            let master_key = proj.get_master_key()?;
-            let vault = vault::Vault::load(&proj.vault_path, master_key)?;
-
-            let repo = resolve_repo_binding(
-                git_info.and_then(|g| g.repo_slug),
-                bound_repo,
-                args.repo.clone(),
-                "push",
-            )
-            .map_err(AppError::from)?;
-
-            if matches!(args.target, targets::Target::Github) && repo.is_none() {
-                return Err(AppError::git(anyhow::anyhow!(
-                    "GitHub push requires a repository. Provide --repo owner/name or initialize inside a git repo so it can be recorded."
-                )));
-            }
-
-            let keys_to_push: Vec<String> = if !args.keys.is_empty() {
+            let proj_config = proj.load_config().ok();
+            let (token, options) =
+                commands::push_options(args.target, proj_config.as_ref(), env, args.repo.clone())?;
+            let repo = options.repo.clone();
+            let remote_env = options.environment.clone();
+
+            let vault = proj.open_vault(env)?;
+
+            let keys_to_push: Vec<String> = if let Some(scope) = args.scope.as_deref() {
+                let cfg = proj_config
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("Failed to read project.toml"))?;
+                project::scope_keys(cfg, scope)?
+            } else if !args.keys.is_empty() {
                 args.keys.clone()
             } else {
                 vault.list().keys().cloned().collect()
@@ -494,6 +962,21 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                 }
             }
 
+            if !args.force {
+                let (expired, _) = expiry::summarize(
+                    filtered
+                        .keys()
+                        .filter_map(|k| vault.get_entry(k).map(|e| (k, e))),
+                    chrono::Utc::now(),
+                );
+                if !expired.is_empty() {
+                    return Err(AppError::user(anyhow::anyhow!(
+                        "Refusing to push expired secrets: {}. Replace them or rerun with --force.",
+                        expired.join(", ")
+                    )));
+                }
+            }
+
             if filtered.is_empty() {
                 if flags.json {
                     let payload = serde_json::json!({
@@ -502,6 +985,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         "data": {
                             "target": format!("{}", args.target),
                             "repo": repo,
+                            "environment": remote_env,
                             "will_create": [],
                             "will_update": [],
                             "will_delete": []
@@ -535,6 +1019,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         "data": {
                             "target": format!("{}", args.target),
                             "repo": repo,
+                            "environment": remote_env,
                             "will_push": dirty.clone(),
                             "unchanged": unchanged.clone(),
                             "will_delete": Vec::<String>::new()
@@ -547,6 +1032,9 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     if let Some(r) = repo.as_ref() {
                         print_out(flags, &format!("Repo: {}", r));
                     }
+                    if let Some(e) = remote_env.as_ref() {
+                        print_out(flags, &format!("Environment: {}", e));
+                    }
                     if !dirty.is_empty() {
                         print_out(flags, &format!("Modified (will push): {:?}", dirty));
                     }
@@ -558,7 +1046,6 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
             }
 
             print_out(flags, &format!("📦 Pushing {} secrets...", filtered.len()));
-            let options = targets::PushOptions { repo };
             if let Err(e) = target_impl.push(&filtered, &token, &options).await {
                 print_err(flags, &format!("x Failed to push: {}", e));
             } else {
@@ -566,12 +1053,14 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
             }
         }
 
+        Commands::Rotate(args) => commands::rotate::handle(args, flags, env).await?,
+
         Commands::Prune(args) => {
             let ci_force_dry = std::env::var("CI").is_ok() && !flags.yes;
             let effective_dry = flags.dry_run || ci_force_dry;
 
             if !effective_dry {
-                require_yes(&flags, "prune")?;
+                require_yes(flags, "prune")?;
             } else if ci_force_dry {
                 print_out(
                     flags,
@@ -595,8 +1084,11 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                 .ok_or_else(|| anyhow::anyhow!("No token for {}", args.target))?;
 
             let proj = project::Project::find()?;
-            let master_key = proj.get_master_key()?;
-            let vault = vault::Vault::load(&proj.vault_path, master_key)?;
+            let vault = proj.open_vault(env)?;
+            let remote_env = proj
+                .load_config()
+                .ok()
+                .and_then(|c| project::target_environment(&c, env, &args.target.to_string()));
 
             let keys_to_prune: Vec<String> = if args.all {
                 let mut ks: Vec<String> = vault.list().keys().cloned().collect();
@@ -642,6 +1134,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         "data": {
                             "target": format!("{}", args.target),
                             "repo": repo,
+                            "environment": remote_env,
                             "will_delete": keys_sorted
                         }
                     });
@@ -654,13 +1147,19 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     if let Some(r) = repo.as_ref() {
                         print_out(flags, &format!("Repo: {}", r));
                     }
+                    if let Some(e) = remote_env.as_ref() {
+                        print_out(flags, &format!("Environment: {}", e));
+                    }
                     print_out(flags, &format!("Will delete: {:?}", keys_sorted));
                 }
                 return Ok(());
             }
 
             print_out(flags, &format!("Deleting from Remote ({})...", args.target));
-            let options = targets::PushOptions { repo };
+            let options = targets::PushOptions {
+                repo,
+                environment: remote_env,
+            };
 
             // ATOMIC: Remote fail stops local delete
             target_impl.delete(&keys_to_prune, &token, &options).await?;
@@ -693,7 +1192,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                 print_out(flags, &format!("Set {}.", key));
             }
             cli::ConfigAction::Unset { key } => {
-                require_yes(&flags, "config unset")?;
+                require_yes(flags, "config unset")?;
                 if flags.dry_run {
                     print_out(flags, &format!("(dry-run) Would unset {}", key));
                     return Ok(());
@@ -716,11 +1215,18 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
             }
         },
 
+        Commands::Key { action } => commands::key::handle(action, flags)?,
+        Commands::Member { action } => commands::member::handle(action, flags)?,
+
+        Commands::Bundle { action } => commands::bundle::handle(action, flags, env)?,
+
         Commands::Project { action } => {
             match action {
                 cli::ProjectAction::Status => {
                     let mut is_project = false;
                     let mut project_name: Option<String> = None;
+                    let environment = env.unwrap_or(project::BASE_ENVIRONMENT).to_string();
+                    let mut environments = vec![project::BASE_ENVIRONMENT.to_string()];
                     let mut vault_exists = false;
                     let mut vault_accessible = false;
                     let mut dirty_count: usize = 0;
@@ -731,13 +1237,16 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     let mut git_bound = false;
                     let mut ready_for_push = false;
                     let mut targets_configured: Vec<String> = Vec::new();
+                    let mut secrets_expired: Vec<String> = Vec::new();
+                    let mut secrets_expiring: Vec<String> = Vec::new();
 
                     let proj = project::Project::find();
                     if let Ok(p) = proj {
                         is_project = true;
-                        vault_exists = p.vault_path.exists();
+                        vault_exists = p.env_vault_path(&environment).exists();
                         let cfg = p.load_config().ok();
                         if let Some(c) = cfg.as_ref() {
+                            environments.extend(c.environments.keys().cloned());
                             if let Some(n) = c.name.clone() {
                                 project_name = Some(n);
                             }
@@ -746,14 +1255,12 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                             git_bound = c.git_repo.is_some();
                         }
 
-                        if vault_exists {
-                            if let Ok(master_key) = p.get_master_key() {
-                                if let Ok(v) = vault::Vault::load(&p.vault_path, master_key) {
-                                    let _ = v.list(); // access to ensure decrypt succeeded
-                                    vault_accessible = true;
-                                    dirty_count = v.dirty_keys().len();
-                                }
-                            }
+                        if vault_exists && let Ok(v) = p.open_vault(env) {
+                            let _ = v.list(); // access to ensure decrypt succeeded
+                            vault_accessible = true;
+                            dirty_count = v.dirty_keys().len();
+                            (secrets_expired, secrets_expiring) =
+                                expiry::summarize(v.effective_entries(), chrono::Utc::now());
                         }
 
                         if let Some(gi) = project::detect_git(None) {
@@ -770,7 +1277,7 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         ready_for_push = is_project
                             && vault_exists
                             && vault_accessible
-                            && (!matches!(git_remote_bound.as_ref(), Some(_))
+                            && (git_remote_bound.as_ref().is_none()
                                 || git_remote_current == git_remote_bound)
                             && !targets_configured.is_empty();
                     }
@@ -779,6 +1286,8 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         let data = ProjectStatusData {
                             is_project,
                             project_name,
+                            environment,
+                            environments,
                             vault_exists,
                             vault_accessible,
                             dirty_count,
@@ -788,6 +1297,8 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                             git_remote_current,
                             git_remote_bound,
                             targets_configured,
+                            secrets_expired,
+                            secrets_expiring,
                             ready_for_push,
                         };
                         let payload = project::project_status_payload(&data);
@@ -796,6 +1307,8 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         println!("Project status:");
                         println!("  is_project: {}", is_project);
                         println!("  project_name: {:?}", project_name);
+                        println!("  environment: {}", environment);
+                        println!("  environments: {:?}", environments);
                         println!("  vault_exists: {}", vault_exists);
                         println!("  vault_accessible: {}", vault_accessible);
                         println!("  dirty_count: {}", dirty_count);
@@ -805,13 +1318,18 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                         println!("  git_remote_current: {:?}", git_remote_current);
                         println!("  git_remote_bound: {:?}", git_remote_bound);
                         println!("  targets_configured: {:?}", targets_configured);
+                        println!("  secrets_expired: {:?}", secrets_expired);
+                        println!("  secrets_expiring: {:?}", secrets_expiring);
                         println!("  ready_for_push: {}", ready_for_push);
                     }
                 }
+                cli::ProjectAction::Environment { action } => {
+                    commands::environment::handle(action, flags)?;
+                }
             }
         }
 
-        Commands::Doctor => {
+        Commands::Doctor { fix } => {
             let version = env!("CARGO_PKG_VERSION").to_string();
 
             let global_config =
@@ -828,21 +1346,126 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                 }
             };
 
-            let (project_detected, vault_accessible) = match project::Project::find() {
+            let mut warnings: Vec<String> = Vec::new();
+            let project = project::Project::find();
+            let mut rotation_overdue = 0;
+            let mut expired: Vec<String> = Vec::new();
+            let mut expiring: Vec<String> = Vec::new();
+            let (project_detected, vault_accessible, key_backed_up) = match &project {
                 Ok(p) => {
-                    let vault_ok = if p.vault_path.exists() {
-                        p.get_master_key()
-                            .ok()
-                            .and_then(|k| vault::Vault::load(&p.vault_path, k).ok())
-                            .is_some()
+                    // Check the environment selected with --env, layered like every other command.
+                    let environment = env.unwrap_or(project::BASE_ENVIRONMENT);
+                    let vault = if p.env_vault_path(environment).exists() {
+                        p.open_vault(env).ok()
                     } else {
-                        false
+                        None
                     };
-                    (true, vault_ok)
+                    if let Some(v) = &vault {
+                        let now = chrono::Utc::now();
+                        rotation_overdue = rotation::due_keys(v, now).len();
+                        (expired, expiring) = expiry::summarize(v.effective_entries(), now);
+                    }
+                    if rotation_overdue > 0 {
+                        warnings.push(format!(
+                            "{} secret(s) are overdue for rotation; run `cred rotate --due`",
+                            rotation_overdue
+                        ));
+                    }
+                    if !expired.is_empty() {
+                        warnings.push(format!(
+                            "Expired secrets: {}; replace them with `cred secret set`",
+                            expired.join(", ")
+                        ));
+                    }
+                    if !expiring.is_empty() {
+                        warnings.push(format!(
+                            "Secrets expiring within {} days: {}",
+                            expiry::WARNING_DAYS,
+                            expiring.join(", ")
+                        ));
+                    }
+                    let vault_ok = vault.is_some();
+                    let backed_up = p
+                        .load_config()
+                        .map(|c| c.key_backup_at.is_some())
+                        .unwrap_or(false);
+                    if !backed_up {
+                        warnings.push(
+                            "Master key has no recovery copy; run `cred key export`".to_string(),
+                        );
+                    }
+                    (true, vault_ok, backed_up)
                 }
-                Err(_) => (false, false),
+                Err(_) => (false, false, false),
             };
 
+            let mut sensitive = config::sensitive_paths();
+            if let Ok(p) = &project {
+                sensitive.extend(p.sensitive_paths());
+            }
+            let mut insecure: Vec<std::path::PathBuf> = sensitive
+                .into_iter()
+                .filter(|p| !fsutil::is_private(p))
+                .collect();
+            let mut tracked = project
+                .as_ref()
+                .map(|p| p.git_tracked_files())
+                .unwrap_or_default();
+            let mut fixed: Vec<String> = Vec::new();
+
+            if fix && flags.dry_run {
+                for path in &insecure {
+                    print_err(
+                        flags,
+                        &format!("(dry-run) Would restrict {} to owner-only", path.display()),
+                    );
+                }
+                if !tracked.is_empty() {
+                    print_err(
+                        flags,
+                        &format!(
+                            "(dry-run) Would untrack {} .cred/ file(s) from git",
+                            tracked.len()
+                        ),
+                    );
+                }
+            } else if fix {
+                for path in insecure.drain(..) {
+                    fsutil::make_private(&path)?;
+                    print_err(
+                        flags,
+                        &format!("✓ Restricted {} to owner-only", path.display()),
+                    );
+                    fixed.push(path.display().to_string());
+                }
+                if let Ok(p) = &project
+                    && !tracked.is_empty()
+                {
+                    p.untrack_files(&tracked)?;
+                    print_err(
+                        flags,
+                        &format!(
+                            "✓ Untracked {} .cred/ file(s) from git; they remain in past commits, so consider `cred key rotate`",
+                            tracked.len()
+                        ),
+                    );
+                    fixed.append(&mut tracked);
+                }
+            }
+            for path in &insecure {
+                warnings.push(format!(
+                    "{} is accessible to other users (mode {}); run `cred doctor --fix`",
+                    path.display(),
+                    fsutil::mode_string(path)
+                ));
+            }
+            if !tracked.is_empty() {
+                warnings.push(format!(
+                    ".cred/ is tracked by git ({} file(s)); run `cred doctor --fix` to untrack it",
+                    tracked.len()
+                ));
+            }
+
             let mut targets: Vec<String> = match config::load() {
                 Ok(c) => c.targets.keys().cloned().collect(),
                 Err(_) => Vec::new(),
@@ -861,8 +1484,19 @@ async fn run(cli: Cli, flags: &CliFlags) -> Result<(), AppError> {
                     "keychain_access": keychain_access,
                     "project_detected": project_detected,
                     "vault_accessible": vault_accessible,
+                    "key_backed_up": key_backed_up,
+                    "rotation_overdue": rotation_overdue,
+                    "secrets_expired": expired,
+                    "secrets_expiring": expiring,
+                    "insecure_permissions": insecure
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<_>>(),
+                    "cred_tracked_by_git": !tracked.is_empty(),
+                    "fixed": fixed,
                     "targets": targets,
-                    "ready_for_push": ready_for_push
+                    "ready_for_push": ready_for_push,
+                    "warnings": warnings
                 }
             });
 
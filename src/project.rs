@@ -3,18 +3,30 @@ use crate::error::{RepoBindingError, RepoBindingErrorKind};
 use anyhow::anyhow;
 use anyhow::{Context, Result, bail};
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, Utc};
 use keyring::Entry;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use uuid::Uuid;
+use zeroize::Zeroize;
 
+use crate::fsutil;
+use crate::keyfile;
+use crate::team;
 use crate::vault;
 
+/// Name of the implicit environment backed by `.cred/vault.enc`.
+pub const BASE_ENVIRONMENT: &str = "base";
+
+/// Header kept at the top of `project.toml` when it is rewritten.
+const PROJECT_TOML_HEADER: &str = "# Cred Project Configuration\n";
+
 /// Project-level metadata stored in `.cred/project.toml`.
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct ProjectConfig {
@@ -23,6 +35,55 @@ pub struct ProjectConfig {
     pub id: Option<Uuid>,
     pub git_root: Option<String>,
     pub git_repo: Option<String>,
+    /// Prior values kept per secret (defaults to `vault::DEFAULT_HISTORY_LIMIT`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_limit: Option<usize>,
+    /// When the master key was last exported or split for recovery (cleared on rotation).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_backup_at: Option<DateTime<Utc>>,
+    /// Named environments layered over the base vault (`[environments.<name>]`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environments: BTreeMap<String, EnvironmentConfig>,
+    /// Named groups of keys (`[scopes] ci = ["DATABASE_URL", ...]`) used to push/export subsets.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scopes: BTreeMap<String, Vec<String>>,
+}
+
+/// A named environment with its own vault file under `.cred/envs/`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct EnvironmentConfig {
+    /// Environment to inherit values from (defaults to the base environment).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherits: Option<String>,
+    /// Per-target remote environment bindings, e.g. `github = "production"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub targets: BTreeMap<String, String>,
+}
+
+/// Suffix of the recovery copies kept while `cred key rotate` re-encrypts vault files.
+const ROTATE_BACKUP_SUFFIX: &str = "pre-rotate";
+
+/// `.gitignore` rules for a team-shared vault: commit the encrypted vaults, project.toml and
+/// the team files; keep the key file, locks and backups local.
+const SHARED_GITIGNORE: &str = "\
+# cred: only the encrypted vaults and team files are committed
+.cred/*
+!.cred/project.toml
+!.cred/vault.enc
+!.cred/recipients
+!.cred/keys.wrapped
+!.cred/envs/
+.cred/envs/*
+!.cred/envs/*.enc
+";
+
+/// Outcome of a master key rotation.
+#[derive(Debug, Clone)]
+pub struct KeyRotation {
+    /// Vault files that were re-encrypted under the new key.
+    pub vault_files: Vec<PathBuf>,
+    /// Where the new key was stored: "keyring" or "passphrase".
+    pub key_storage: &'static str,
 }
 
 /// Holds paths to project resources under `.cred/`.
@@ -45,6 +106,8 @@ pub struct GitInfo {
 pub struct ProjectStatusData {
     pub is_project: bool,
     pub project_name: Option<String>,
+    pub environment: String,
+    pub environments: Vec<String>,
     pub vault_exists: bool,
     pub vault_accessible: bool,
     pub dirty_count: usize,
@@ -54,6 +117,10 @@ pub struct ProjectStatusData {
     pub git_remote_current: Option<String>,
     pub git_remote_bound: Option<String>,
     pub targets_configured: Vec<String>,
+    /// Keys whose value has passed its expiry date.
+    pub secrets_expired: Vec<String>,
+    /// Keys expiring within `expiry::WARNING_DAYS`.
+    pub secrets_expiring: Vec<String>,
     pub ready_for_push: bool,
 }
 
@@ -86,7 +153,372 @@ impl Project {
         Ok(config)
     }
 
-    /// Fetch the 32-byte master key for this project (env override for CI, else OS keyring).
+    /// Hold the project.toml lock for a load-modify-save cycle.
+    pub fn lock_config(&self) -> Result<fsutil::FileLock> {
+        fsutil::lock(&self.config_path)
+    }
+
+    /// Persist the project configuration back to `.cred/project.toml`.
+    /// Callers that loaded the config to modify it should hold `lock_config`.
+    pub fn save_config(&self, config: &ProjectConfig) -> Result<()> {
+        let body = toml::to_string_pretty(config).context("Failed to serialize project.toml")?;
+        fsutil::write_atomic(
+            &self.config_path,
+            format!("{}{}", PROJECT_TOML_HEADER, body).as_bytes(),
+        )
+        .context("Failed to write project.toml")
+    }
+
+    /// The `.cred/` directory holding this project's files.
+    pub fn cred_dir(&self) -> &Path {
+        self.config_path
+            .parent()
+            .unwrap_or_else(|| Path::new(".cred"))
+    }
+
+    /// Vault file for a named environment (the base environment uses `vault.enc`).
+    pub fn env_vault_path(&self, env: &str) -> PathBuf {
+        if env == BASE_ENVIRONMENT {
+            return self.vault_path.clone();
+        }
+        self.cred_dir().join("envs").join(format!("{}.enc", env))
+    }
+
+    /// Open the vault for `env` (or the base vault), layering each environment over
+    /// the one it inherits from so reads see inherited values and writes stay local.
+    pub fn open_vault(&self, env: Option<&str>) -> Result<vault::Vault> {
+        let key = self.get_master_key()?;
+        let config = self.load_config()?;
+        let chain = resolve_environment_chain(&config, env)?;
+
+        let mut layered: Option<vault::Vault> = None;
+        for name in chain.iter().rev() {
+            let v = vault::Vault::load_for_project(&self.env_vault_path(name), key, config.id)
+                .with_context(|| format!("Failed to open vault for environment '{}'", name))?;
+            layered = Some(match layered {
+                Some(parent) => v.with_parent(parent),
+                None => v,
+            });
+        }
+        let limit = config.history_limit.unwrap_or(vault::DEFAULT_HISTORY_LIMIT);
+        layered
+            .map(|v| v.with_history_limit(limit))
+            .ok_or_else(|| anyhow!("No vault to open"))
+    }
+
+    /// Every vault file on disk: the base vault plus one per declared environment.
+    pub fn vault_files(&self, config: &ProjectConfig) -> Vec<PathBuf> {
+        std::iter::once(BASE_ENVIRONMENT)
+            .chain(config.environments.keys().map(String::as_str))
+            .map(|env| self.env_vault_path(env))
+            .filter(|p| p.exists())
+            .collect()
+    }
+
+    /// Existing project files and directories that only their owner should be able to read.
+    pub fn sensitive_paths(&self) -> Vec<PathBuf> {
+        let config = self.load_config().unwrap_or_default();
+        let mut paths = vec![
+            self.cred_dir().to_path_buf(),
+            self.cred_dir().join("envs"),
+            self.config_path.clone(),
+            self.key_file_path(),
+        ];
+        for file in self.vault_files(&config) {
+            paths.push(vault::backup_path(&file));
+            paths.push(file);
+        }
+        paths.retain(|p| p.exists());
+        paths
+    }
+
+    /// Files under `.cred/` tracked by git, except the files meant to be committed: the team
+    /// files, plus the encrypted vaults and project.toml once the vault is team-shared.
+    pub fn git_tracked_files(&self) -> Vec<String> {
+        let Some(root) = self.cred_dir().parent() else {
+            return Vec::new();
+        };
+        let shared = self.wrapped_keys_path().exists();
+        Command::new("git")
+            .args(["ls-files", "--", ".cred"])
+            .current_dir(root)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| {
+                String::from_utf8_lossy(&o.stdout)
+                    .lines()
+                    .filter(|f| !is_committable(f, shared))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Remove `files` from the git index (keeping them on disk) and ignore `.cred/`.
+    pub fn untrack_files(&self, files: &[String]) -> Result<()> {
+        let root = self
+            .cred_dir()
+            .parent()
+            .ok_or_else(|| anyhow!("Project has no root directory"))?;
+        let status = Command::new("git")
+            .args(["rm", "--cached", "--quiet", "--"])
+            .args(files)
+            .current_dir(root)
+            .status()
+            .context("Failed to run git")?;
+        if !status.success() {
+            bail!("git rm --cached failed");
+        }
+        update_gitignore(root)
+    }
+
+    /// Let git commit the team-shared files while the rest of `.cred/` stays ignored.
+    /// Returns whether `.gitignore` changed.
+    pub fn share_gitignore(&self) -> Result<bool> {
+        let root = self
+            .cred_dir()
+            .parent()
+            .ok_or_else(|| anyhow!("Project has no root directory"))?;
+        share_gitignore(root)
+    }
+
+    /// Replace the master key: re-encrypt every vault file (history included) under a
+    /// fresh key, verify the result, then store the new key where the old one lived.
+    ///
+    /// A recovery copy of each old ciphertext (`<file>.pre-rotate`) is written first and
+    /// restored if anything fails before the new key is stored; copies are removed only
+    /// once the new ciphertext has been verified.
+    pub fn rotate_master_key(&self) -> Result<KeyRotation> {
+        if std::env::var("CRED_MASTER_KEY_B64").is_ok() {
+            bail!(
+                "CRED_MASTER_KEY_B64 is set; unset it so the rotated key can be stored in the keyring or key file"
+            );
+        }
+        let mut passphrase = if self.key_file_path().exists() {
+            Some(keyfile::read_passphrase("Enter vault passphrase: ", false)?)
+        } else {
+            None
+        };
+        let result = self.rotate_master_key_with(passphrase.as_deref());
+        passphrase.zeroize();
+        result
+    }
+
+    /// Rotation with the key file passphrase already in hand (`None` for keyring projects).
+    /// project.toml and every vault stay locked until the new key is stored, so no other
+    /// writer can save under the old key mid-rotation.
+    pub fn rotate_master_key_with(&self, passphrase: Option<&str>) -> Result<KeyRotation> {
+        let _config_lock = self.lock_config()?;
+        let config = self.load_config()?;
+        let files = self.vault_files(&config);
+        let _vault_locks = files
+            .iter()
+            .map(|path| fsutil::lock(path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let key_file = self.key_file_path();
+        let old_key = match passphrase {
+            Some(p) if !self.wrapped_keys_path().exists() => {
+                keyfile::WrappedKey::read(&key_file)?.open(p)?
+            }
+            _ => self.get_master_key()?,
+        };
+        let mut new_key = [0u8; 32];
+        rand::rng().fill_bytes(&mut new_key);
+
+        let result = self.reencrypt_and_store(&files, old_key, new_key, passphrase);
+        new_key.zeroize();
+        result?;
+
+        for path in &files {
+            let _ = fs::remove_file(rotate_backup_path(path));
+            // The save-time backup still holds the old ciphertext; refresh it.
+            if path.exists() {
+                fsutil::write_atomic(&vault::backup_path(path), &fs::read(path)?)?;
+            }
+        }
+        // Existing recovery copies hold the old key.
+        let mut config = self.load_config()?;
+        if config.key_backup_at.take().is_some() {
+            self.save_config(&config)?;
+        }
+        Ok(KeyRotation {
+            vault_files: files,
+            key_storage: self.key_storage(),
+        })
+    }
+
+    /// Where `get_master_key` reads this project's key from.
+    pub fn key_storage(&self) -> &'static str {
+        if self.wrapped_keys_path().exists() {
+            "team"
+        } else if self.key_file_path().exists() {
+            "passphrase"
+        } else {
+            "keyring"
+        }
+    }
+
+    /// Put a recovered master key where `get_master_key` will find it: re-wrapped under
+    /// `passphrase` when the project uses a key file (required then), re-sealed for the
+    /// team when shared, otherwise in the OS keyring.
+    pub fn restore_master_key(&self, key: &[u8; 32], passphrase: Option<&str>) -> Result<()> {
+        if passphrase.is_none() && self.key_file_path().exists() {
+            bail!(
+                "This project keeps its key in a passphrase-protected key file; a new passphrase is required"
+            );
+        }
+        self.store_master_key(key, passphrase)
+    }
+
+    fn reencrypt_and_store(
+        &self,
+        files: &[PathBuf],
+        old_key: [u8; 32],
+        new_key: [u8; 32],
+        passphrase: Option<&str>,
+    ) -> Result<()> {
+        for path in files {
+            fs::copy(path, rotate_backup_path(path))
+                .with_context(|| format!("Failed to write recovery copy of {}", path.display()))?;
+        }
+        let restore = || {
+            for path in files {
+                let backup = rotate_backup_path(path);
+                if backup.exists() {
+                    let _ = fs::rename(&backup, path);
+                }
+            }
+        };
+
+        let rewrite = || -> Result<()> {
+            for path in files {
+                // A rekey must not prune history, whatever the project's history_limit.
+                let mut v = vault::Vault::load(path, old_key)?.with_history_limit(usize::MAX);
+                let before = comparable_entries(&v)?;
+                v.rekey(new_key);
+                v.save()?;
+                let check = vault::Vault::load(path, new_key).with_context(|| {
+                    format!("Re-encrypted {} failed verification", path.display())
+                })?;
+                if comparable_entries(&check)? != before {
+                    bail!(
+                        "Re-encrypted {} does not match the original",
+                        path.display()
+                    );
+                }
+            }
+            self.store_master_key(&new_key, passphrase)
+        };
+        rewrite().inspect_err(|_| restore())
+    }
+
+    /// Persist `key` everywhere this project keeps it: re-wrapped in the key file when a
+    /// passphrase is given, re-sealed for team recipients when shared, otherwise in the
+    /// OS keyring.
+    fn store_master_key(&self, key: &[u8; 32], passphrase: Option<&str>) -> Result<()> {
+        let shared = self.wrapped_keys_path().exists();
+        if shared {
+            let recipients = team::read_recipients(&self.recipients_path())?;
+            team::WrappedKeys::seal(key, &recipients)?.write(&self.wrapped_keys_path())?;
+        }
+        if let Some(p) = passphrase {
+            let key_file = self.key_file_path();
+            let params = keyfile::WrappedKey::read(&key_file)?.params();
+            return keyfile::WrappedKey::seal(key, p, params)?.write(&key_file);
+        }
+        if shared {
+            return Ok(());
+        }
+        let config = self.load_config()?;
+        let project_id = config
+            .id
+            .ok_or_else(|| anyhow::anyhow!("Project ID missing in project.toml"))?;
+        store_keyring_key(&project_id, key)
+    }
+
+    /// Note in project.toml that the master key now has a recovery copy.
+    pub fn record_key_backup(&self) -> Result<()> {
+        let _lock = self.lock_config()?;
+        let mut config = self.load_config()?;
+        config.key_backup_at = Some(Utc::now());
+        self.save_config(&config)
+    }
+
+    /// Path of the team recipients list (`.cred/recipients`).
+    pub fn recipients_path(&self) -> PathBuf {
+        self.cred_dir().join(team::RECIPIENTS_FILE)
+    }
+
+    /// Path of the project key sealed for team recipients (`.cred/keys.wrapped`).
+    pub fn wrapped_keys_path(&self) -> PathBuf {
+        self.cred_dir().join(team::WRAPPED_KEYS_FILE)
+    }
+
+    /// Add a team member and re-seal the project key for all recipients. The first
+    /// member added also enrols the caller, so they keep access once the vault is shared.
+    pub fn add_member(&self, name: &str, public_key: &str) -> Result<Vec<team::Recipient>> {
+        let _ = team::parse_public_key(public_key)?;
+        let mut key = self.get_master_key()?;
+        let mut recipients = team::read_recipients(&self.recipients_path())?;
+        if recipients.is_empty() {
+            let me = team::load_or_create_identity()?;
+            let my_name = env::var("USER").unwrap_or_else(|_| "owner".to_string());
+            if me.public_b64() != public_key {
+                recipients.push(team::Recipient {
+                    name: my_name,
+                    public_key: me.public_b64(),
+                });
+            }
+        }
+        if recipients.iter().any(|r| r.name == name) {
+            key.zeroize();
+            bail!("A member named '{}' already exists", name);
+        }
+        if recipients.iter().any(|r| r.public_key == public_key) {
+            key.zeroize();
+            bail!("That public key is already a recipient");
+        }
+        recipients.push(team::Recipient {
+            name: name.to_string(),
+            public_key: public_key.to_string(),
+        });
+        let sealed = team::WrappedKeys::seal(&key, &recipients);
+        key.zeroize();
+        team::write_recipients(&self.recipients_path(), &recipients)?;
+        sealed?.write(&self.wrapped_keys_path())?;
+        Ok(recipients)
+    }
+
+    /// Remove a team member and re-seal the project key for the remaining recipients.
+    /// The removed member may still hold the old key; rotate it to revoke access fully.
+    pub fn remove_member(&self, name: &str) -> Result<Vec<team::Recipient>> {
+        let mut recipients = team::read_recipients(&self.recipients_path())?;
+        let before = recipients.len();
+        recipients.retain(|r| r.name != name);
+        if recipients.len() == before {
+            bail!("No member named '{}'", name);
+        }
+        if recipients.is_empty() {
+            bail!("Refusing to remove the last member; nobody could decrypt the vault");
+        }
+        let mut key = self.get_master_key()?;
+        let sealed = team::WrappedKeys::seal(&key, &recipients);
+        key.zeroize();
+        team::write_recipients(&self.recipients_path(), &recipients)?;
+        sealed?.write(&self.wrapped_keys_path())?;
+        Ok(recipients)
+    }
+
+    /// Path of the passphrase-wrapped key file (present only for passphrase projects).
+    pub fn key_file_path(&self) -> PathBuf {
+        self.cred_dir().join(keyfile::KEY_FILE_NAME)
+    }
+
+    /// Fetch the 32-byte master key for this project: env override for CI, then the team's
+    /// `keys.wrapped` once the vault is shared (a local key file may be stale after a
+    /// teammate rotates), then the passphrase-wrapped key file, else the OS keyring.
     pub fn get_master_key(&self) -> Result<[u8; 32]> {
         // Check for key in env for CI and testing
         if let Ok(b64) = std::env::var("CRED_MASTER_KEY_B64") {
@@ -100,6 +532,24 @@ impl Project {
             key.copy_from_slice(&bytes);
             return Ok(key);
         }
+        let key_file = self.key_file_path();
+        let wrapped_keys = self.wrapped_keys_path();
+        if wrapped_keys.exists() {
+            match team::load_identity()? {
+                Some(identity) => return team::WrappedKeys::read(&wrapped_keys)?.open(&identity),
+                None if !key_file.exists() => bail!(
+                    "This vault is shared with a team but you have no identity yet. Run `cred member identity` and ask a member to add you."
+                ),
+                None => {}
+            }
+        }
+        if key_file.exists() {
+            let wrapped = keyfile::WrappedKey::read(&key_file)?;
+            let mut passphrase = keyfile::read_passphrase("Enter vault passphrase: ", false)?;
+            let key = wrapped.open(&passphrase);
+            passphrase.zeroize();
+            return key;
+        }
         let config = self.load_config()?;
         let project_id = config
             .id
@@ -123,13 +573,145 @@ impl Project {
         Ok(key)
     }
 
-    #[allow(dead_code)]
-    // Later feature
-    pub fn add_key_to_scopes(&self, _scope_names: &[String], _key: &str) -> Result<()> {
+    /// Add `key` to each named scope in project.toml, creating scopes as needed.
+    pub fn add_key_to_scopes(&self, scope_names: &[String], key: &str) -> Result<()> {
+        if scope_names.is_empty() {
+            return Ok(());
+        }
+        validate_scope_names(scope_names)?;
+        let _lock = self.lock_config()?;
+        let mut config = self.load_config()?;
+        for name in scope_names {
+            let keys = config.scopes.entry(name.clone()).or_default();
+            if !keys.iter().any(|k| k == key) {
+                keys.push(key.to_string());
+                keys.sort();
+            }
+        }
+        self.save_config(&config)
+    }
+
+    /// Drop `key` from every scope (e.g. after the secret is removed). Empty scopes are kept.
+    pub fn remove_key_from_scopes(&self, key: &str) -> Result<()> {
+        let _lock = self.lock_config()?;
+        let mut config = self.load_config()?;
+        let mut changed = false;
+        for keys in config.scopes.values_mut() {
+            let before = keys.len();
+            keys.retain(|k| k != key);
+            changed |= keys.len() != before;
+        }
+        if changed {
+            self.save_config(&config)?;
+        }
         Ok(())
     }
 }
 
+/// A vault's own entries, history included, with the hashes `save` recomputes dropped.
+fn comparable_entries(v: &vault::Vault) -> Result<serde_json::Value> {
+    let mut entries = v.list_entries().clone();
+    for entry in entries.values_mut() {
+        entry.hash = None;
+    }
+    Ok(serde_json::to_value(entries)?)
+}
+
+/// Store a project's master key in the OS keyring (service "cred-cli", user = project id).
+pub fn store_keyring_key(project_id: &Uuid, key: &[u8; 32]) -> Result<()> {
+    // Keyring stores strings, so we base64 encode the raw key
+    let mut key_b64 = BASE64.encode(key);
+    let res = Entry::new("cred-cli", &project_id.to_string())?
+        .set_password(&key_b64)
+        .context("Failed to save key to the System Credential Store");
+    key_b64.zeroize();
+    res
+}
+
+fn rotate_backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ROTATE_BACKUP_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Resolve `env` to its inheritance chain, most specific first and ending with the
+/// base environment. Errors on unknown names and inheritance cycles.
+pub fn resolve_environment_chain(config: &ProjectConfig, env: Option<&str>) -> Result<Vec<String>> {
+    let mut chain = Vec::new();
+    let mut current = env.unwrap_or(BASE_ENVIRONMENT).to_string();
+    while current != BASE_ENVIRONMENT {
+        if chain.contains(&current) {
+            bail!("Environment inheritance cycle involving '{}'", current);
+        }
+        let cfg = config.environments.get(&current).ok_or_else(|| {
+            anyhow!(
+                "Unknown environment '{}'. Add it with `cred project environment add {}`.",
+                current,
+                current
+            )
+        })?;
+        let next = cfg
+            .inherits
+            .clone()
+            .unwrap_or_else(|| BASE_ENVIRONMENT.to_string());
+        chain.push(current);
+        current = next;
+    }
+    chain.push(BASE_ENVIRONMENT.to_string());
+    Ok(chain)
+}
+
+/// Validate an environment or scope name for use as a file name and TOML key.
+pub fn validate_name(kind: &str, name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!(
+            "Invalid {} name '{}': use letters, digits, '-' or '_'",
+            kind,
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Check every `--scope` name up front, so a bad one fails before anything is written.
+pub fn validate_scope_names(names: &[String]) -> Result<()> {
+    names
+        .iter()
+        .try_for_each(|name| validate_name("scope", name))
+}
+
+/// Keys belonging to a scope, sorted. Errors if the scope is not declared.
+pub fn scope_keys(config: &ProjectConfig, scope: &str) -> Result<Vec<String>> {
+    let mut keys = config.scopes.get(scope).cloned().ok_or_else(|| {
+        anyhow!(
+            "Unknown scope '{}'. Add keys to it with `cred secret set KEY VALUE --scope {}`.",
+            scope,
+            scope
+        )
+    })?;
+    keys.sort();
+    keys.dedup();
+    Ok(keys)
+}
+
+/// Remote environment bound to `target` for `env`, if configured.
+pub fn target_environment(
+    config: &ProjectConfig,
+    env: Option<&str>,
+    target: &str,
+) -> Option<String> {
+    config
+        .environments
+        .get(env?)
+        .and_then(|e| e.targets.get(target))
+        .cloned()
+}
+
 pub fn init() -> Result<()> {
     let current_dir = env::current_dir().context("Failed to get current directory")?;
     init_at(&current_dir)
@@ -137,11 +719,17 @@ pub fn init() -> Result<()> {
 
 /// Initialize a project at the given root, creating `.cred/`, key, vault, and project.toml.
 pub fn init_at(root: &Path) -> Result<()> {
+    init_at_with(root, None)
+}
+
+/// Like `init_at`, but with `passphrase` the key is wrapped into `.cred/master.key`
+/// instead of being stored in the OS keyring.
+pub fn init_at_with(root: &Path, passphrase: Option<&str>) -> Result<()> {
     let cred_dir = root.join(".cred");
     if cred_dir.exists() {
         bail!("Cred is already initialized here: {}", cred_dir.display());
     }
-    fs::create_dir(&cred_dir).context("Failed to create .cred directory")?;
+    fsutil::create_private_dir(&cred_dir).context("Failed to create .cred directory")?;
 
     let project_id = Uuid::new_v4();
 
@@ -170,36 +758,41 @@ id = "{}"
 {}{}"#,
         project_id, git_root_line, git_repo_line
     );
-    fs::write(cred_dir.join("project.toml"), project_toml)?;
+    fsutil::write_atomic(&cred_dir.join("project.toml"), project_toml.as_bytes())?;
 
     let mut key = [0u8; 32];
     rand::rng().fill_bytes(&mut key);
 
-    // Service: "cred-cli", User: project_id
-    let entry = Entry::new("cred-cli", &project_id.to_string())?;
-
-    // Keyring stores strings, so we base64 encode the raw key
-    let key_b64 = BASE64.encode(key);
-    entry
-        .set_password(&key_b64)
-        .context("Failed to save key to the System Credential Store")?;
-
-    key.fill(0);
+    match passphrase {
+        Some(p) => {
+            keyfile::WrappedKey::seal(&key, p, keyfile::KdfParams::default())?
+                .write(&cred_dir.join(keyfile::KEY_FILE_NAME))?;
+        }
+        None => store_keyring_key(&project_id, &key)?,
+    }
 
     // Create an empty encrypted vault to ensure presence
     {
         let vault_path = cred_dir.join("vault.enc");
-        let v = vault::Vault::load(&vault_path, key)?;
+        let v = vault::Vault::load_for_project(&vault_path, key, Some(project_id))?;
         v.save()?;
     }
+    key.fill(0);
 
     update_gitignore(root)?;
 
     println!("Initialized new cred project at {}", cred_dir.display());
-    println!(
-        "🔑 Encryption key generated and stored in the System Credential Store (ID: {})",
-        project_id
-    );
+    if passphrase.is_some() {
+        println!(
+            "🔑 Encryption key generated and protected by your passphrase ({})",
+            cred_dir.join(keyfile::KEY_FILE_NAME).display()
+        );
+    } else {
+        println!(
+            "🔑 Encryption key generated and stored in the System Credential Store (ID: {})",
+            project_id
+        );
+    }
     Ok(())
 }
 
@@ -212,48 +805,48 @@ pub fn resolve_repo_binding(
     verb: &str,
 ) -> Result<Option<String>, RepoBindingError> {
     if let Some(r) = provided.clone() {
-        if let Some(bound_repo) = bound.as_ref() {
-            if bound_repo != &r {
-                return Err(RepoBindingError {
-                    kind: RepoBindingErrorKind::Git,
-                    error: anyhow!(
-                        "Refusing to {}: provided --repo '{}' does not match bound repo '{}'.",
-                        verb,
-                        r,
-                        bound_repo
-                    ),
-                });
-            }
+        if let Some(bound_repo) = bound.as_ref()
+            && bound_repo != &r
+        {
+            return Err(RepoBindingError {
+                kind: RepoBindingErrorKind::Git,
+                error: anyhow!(
+                    "Refusing to {}: provided --repo '{}' does not match bound repo '{}'.",
+                    verb,
+                    r,
+                    bound_repo
+                ),
+            });
         }
-        if let Some(live) = detected.as_ref() {
-            if live != &r {
-                return Err(RepoBindingError {
-                    kind: RepoBindingErrorKind::User,
-                    error: anyhow!(
-                        "Refusing to {}: provided --repo '{}' does not match detected repo '{}'.",
-                        verb,
-                        r,
-                        live
-                    ),
-                });
-            }
+        if let Some(live) = detected.as_ref()
+            && live != &r
+        {
+            return Err(RepoBindingError {
+                kind: RepoBindingErrorKind::User,
+                error: anyhow!(
+                    "Refusing to {}: provided --repo '{}' does not match detected repo '{}'.",
+                    verb,
+                    r,
+                    live
+                ),
+            });
         }
         return Ok(Some(r));
     }
 
     if let Some(live) = detected.clone() {
-        if let Some(bound_repo) = bound.as_ref() {
-            if bound_repo != &live {
-                return Err(RepoBindingError {
-                    kind: RepoBindingErrorKind::Git,
-                    error: anyhow!(
-                        "Refusing to {}: detected repo '{}' does not match bound repo '{}'.",
-                        verb,
-                        live,
-                        bound_repo
-                    ),
-                });
-            }
+        if let Some(bound_repo) = bound.as_ref()
+            && bound_repo != &live
+        {
+            return Err(RepoBindingError {
+                kind: RepoBindingErrorKind::Git,
+                error: anyhow!(
+                    "Refusing to {}: detected repo '{}' does not match bound repo '{}'.",
+                    verb,
+                    live,
+                    bound_repo
+                ),
+            });
         }
         return Ok(Some(live));
     }
@@ -269,6 +862,8 @@ pub fn project_status_payload(data: &ProjectStatusData) -> serde_json::Value {
         "data": {
             "is_project": data.is_project,
             "project_name": data.project_name,
+            "environment": data.environment,
+            "environments": data.environments,
             "vault_exists": data.vault_exists,
             "vault_accessible": data.vault_accessible,
             "dirty_count": data.dirty_count,
@@ -278,6 +873,8 @@ pub fn project_status_payload(data: &ProjectStatusData) -> serde_json::Value {
             "git_remote_current": data.git_remote_current,
             "git_remote_bound": data.git_remote_bound,
             "targets_configured": data.targets_configured,
+            "secrets_expired": data.secrets_expired,
+            "secrets_expiring": data.secrets_expiring,
             "ready_for_push": data.ready_for_push
         }
     })
@@ -291,10 +888,8 @@ fn normalize_github_remote(remote: &str) -> Option<String> {
         stripped
     } else if let Some(stripped) = trimmed.strip_prefix("ssh://git@github.com/") {
         stripped
-    } else if let Some(stripped) = trimmed.strip_prefix("https://github.com/") {
-        stripped
     } else {
-        return None;
+        trimmed.strip_prefix("https://github.com/")?
     };
 
     let mut parts = remainder.split('/');
@@ -347,12 +942,46 @@ pub fn detect_git(base: Option<&Path>) -> Option<GitInfo> {
     })
 }
 
+/// Whether `file` (a git path under `.cred/`) is meant to be committed.
+fn is_committable(file: &str, shared: bool) -> bool {
+    if file.ends_with(team::RECIPIENTS_FILE) || file.ends_with(team::WRAPPED_KEYS_FILE) {
+        return true;
+    }
+    shared
+        && (file == ".cred/project.toml"
+            || file == ".cred/vault.enc"
+            || file
+                .strip_prefix(".cred/envs/")
+                .is_some_and(|name| !name.contains('/') && name.ends_with(".enc")))
+}
+
+/// Replace a blanket `.cred/` ignore with `SHARED_GITIGNORE`, so the shared vault can be
+/// committed without also committing the key file, locks or backups. Returns whether
+/// `.gitignore` changed.
+fn share_gitignore(root: &Path) -> Result<bool> {
+    let gitignore = root.join(".gitignore");
+    let content = fs::read_to_string(&gitignore).unwrap_or_default();
+    if content.lines().any(|l| l.trim() == "!.cred/keys.wrapped") {
+        return Ok(false);
+    }
+    let kept: Vec<&str> = content
+        .lines()
+        .filter(|l| !matches!(l.trim(), ".cred" | ".cred/"))
+        .collect();
+    let mut updated = kept.join("\n").trim_end().to_string();
+    if !updated.is_empty() {
+        updated.push_str("\n\n");
+    }
+    updated.push_str(SHARED_GITIGNORE);
+    fs::write(&gitignore, updated).context("Failed to update .gitignore")?;
+    Ok(true)
+}
+
 /// Ensure `.cred/` is ignored in the repository.
 fn update_gitignore(root: &Path) -> Result<()> {
     let gitignore = root.join(".gitignore");
     let entry = "\n.cred/\n";
     let mut file = fs::OpenOptions::new()
-        .write(true)
         .append(true)
         .create(true)
         .open(&gitignore)?;
@@ -372,6 +1001,58 @@ fn update_gitignore(root: &Path) -> Result<()> {
 mod tests {
     use super::*;
 
+    // Sharing swaps the blanket `.cred/` ignore for rules that leave only the shared files.
+    #[test]
+    fn test_share_gitignore_keeps_local_files_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(root)
+            .status()
+            .unwrap();
+        fs::write(root.join(".gitignore"), "target/\n\n.cred/\n").unwrap();
+        fs::create_dir_all(root.join(".cred/envs")).unwrap();
+
+        assert!(share_gitignore(root).unwrap());
+        assert!(!share_gitignore(root).unwrap());
+        let content = fs::read_to_string(root.join(".gitignore")).unwrap();
+        assert!(content.starts_with("target/\n"));
+        assert!(!content.lines().any(|l| l == ".cred/"));
+
+        let ignored = |file: &str| {
+            Command::new("git")
+                .args(["check-ignore", "-q", "--no-index", file])
+                .current_dir(root)
+                .status()
+                .unwrap()
+                .success()
+        };
+        for file in [
+            ".cred/project.toml",
+            ".cred/vault.enc",
+            ".cred/recipients",
+            ".cred/keys.wrapped",
+            ".cred/envs/prod.enc",
+        ] {
+            assert!(!ignored(file), "{file} should be committable");
+            assert!(is_committable(file, true));
+        }
+        for file in [
+            ".cred/master.key",
+            ".cred/vault.enc.lock",
+            ".cred/vault.enc.bak",
+            ".cred/vault.enc.pre-rotate",
+            ".cred/project.toml.lock",
+            ".cred/envs/prod.enc.lock",
+            ".cred/envs/prod.enc.bak",
+        ] {
+            assert!(ignored(file), "{file} should stay ignored");
+            assert!(!is_committable(file, true));
+        }
+        assert!(!is_committable(".cred/vault.enc", false));
+    }
+
     #[test]
     fn test_resolve_repo_binding_matches_detected() {
         let detected = Some("org/repo".to_string());
@@ -411,11 +1092,80 @@ mod tests {
         assert!(matches!(res.unwrap_err().kind, RepoBindingErrorKind::Git));
     }
 
+    #[test]
+    fn test_environment_chain_resolution() {
+        let mut config = ProjectConfig::default();
+        config
+            .environments
+            .insert("staging".to_string(), EnvironmentConfig::default());
+        config.environments.insert(
+            "prod".to_string(),
+            EnvironmentConfig {
+                inherits: Some("staging".to_string()),
+                targets: BTreeMap::from([("github".to_string(), "production".to_string())]),
+            },
+        );
+
+        assert_eq!(
+            resolve_environment_chain(&config, None).unwrap(),
+            vec!["base"]
+        );
+        assert_eq!(
+            resolve_environment_chain(&config, Some("prod")).unwrap(),
+            vec!["prod", "staging", "base"]
+        );
+        assert!(resolve_environment_chain(&config, Some("qa")).is_err());
+        assert_eq!(
+            target_environment(&config, Some("prod"), "github"),
+            Some("production".to_string())
+        );
+        assert_eq!(target_environment(&config, Some("staging"), "github"), None);
+
+        config.environments.get_mut("staging").unwrap().inherits = Some("prod".to_string());
+        assert!(resolve_environment_chain(&config, Some("prod")).is_err());
+    }
+
+    #[test]
+    fn test_scopes_round_trip_through_project_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        init_at(dir.path()).unwrap();
+        let cred_dir = dir.path().join(".cred");
+        let proj = Project {
+            vault_path: cred_dir.join("vault.enc"),
+            config_path: cred_dir.join("project.toml"),
+        };
+
+        proj.add_key_to_scopes(&["ci".to_string(), "worker".to_string()], "DB_URL")
+            .unwrap();
+        proj.add_key_to_scopes(&["ci".to_string()], "API_KEY")
+            .unwrap();
+        proj.add_key_to_scopes(&["ci".to_string()], "API_KEY")
+            .unwrap();
+
+        let cfg = proj.load_config().unwrap();
+        assert!(cfg.id.is_some());
+        assert_eq!(scope_keys(&cfg, "ci").unwrap(), vec!["API_KEY", "DB_URL"]);
+        assert_eq!(scope_keys(&cfg, "worker").unwrap(), vec!["DB_URL"]);
+        assert!(scope_keys(&cfg, "frontend").is_err());
+        assert!(
+            proj.add_key_to_scopes(&["bad scope".to_string()], "X")
+                .is_err()
+        );
+        assert!(validate_scope_names(&["ci".to_string(), "bad scope".to_string()]).is_err());
+
+        proj.remove_key_from_scopes("DB_URL").unwrap();
+        let cfg = proj.load_config().unwrap();
+        assert_eq!(scope_keys(&cfg, "ci").unwrap(), vec!["API_KEY"]);
+        assert!(scope_keys(&cfg, "worker").unwrap().is_empty());
+    }
+
     #[test]
     fn test_project_status_payload_schema() {
         let data = ProjectStatusData {
             is_project: true,
             project_name: Some("myapp".to_string()),
+            environment: "prod".to_string(),
+            environments: vec!["base".to_string(), "prod".to_string()],
             vault_exists: true,
             vault_accessible: true,
             dirty_count: 2,
@@ -425,6 +1175,8 @@ mod tests {
             git_remote_current: Some("org/repo".to_string()),
             git_remote_bound: Some("org/repo".to_string()),
             targets_configured: vec!["github".to_string()],
+            secrets_expired: vec!["OLD_PAT".to_string()],
+            secrets_expiring: Vec::new(),
             ready_for_push: true,
         };
         let payload = project_status_payload(&data);
@@ -436,6 +1188,11 @@ mod tests {
             assert_eq!(data_val.get("project_name").unwrap(), "myapp");
             assert_eq!(data_val.get("git_remote_current").unwrap(), "org/repo");
             assert_eq!(data_val.get("dirty_count").unwrap(), 2);
+            assert_eq!(data_val.get("environment").unwrap(), "prod");
+            assert_eq!(
+                data_val.get("secrets_expired").unwrap(),
+                &serde_json::json!(["OLD_PAT"])
+            );
         } else {
             panic!("Payload is not an object");
         }
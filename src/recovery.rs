@@ -0,0 +1,405 @@
+//! Master key backups: passphrase-encrypted recovery blobs (`cred key export/import`)
+//! and Shamir shares (`cred key split/combine`).
+//!
+//! A blob is the project id plus the key wrapped like `.cred/master.key`, followed by a
+//! short checksum so typos in a hand-copied backup are reported as such rather than as
+//! a wrong passphrase. It is written as an ASCII-armored block, either base64 or, for
+//! paper, grouped uppercase hex (which also fits QR alphanumeric mode).
+//!
+//! Shares use the same armor. Each carries the project id and threshold so shares from
+//! different projects (or different splits) cannot be combined by mistake.
+
+use anyhow::{Context, Result, anyhow, bail};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use blahaj::{Share, Sharks};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use zeroize::Zeroize;
+
+use crate::keyfile::{KdfParams, WrappedKey};
+
+const BLOB_VERSION: u8 = 1;
+const CHECKSUM_LEN: usize = 4;
+const RECOVERY_LABEL: &str = "CRED RECOVERY KEY";
+const SHARE_LABEL: &str = "CRED KEY SHARE";
+const SHARE_HEADER_LEN: usize = 1 + 16 + 1 + 1;
+
+/// A passphrase-protected copy of a project's master key.
+pub struct RecoveryBlob {
+    pub project_id: Uuid,
+    wrapped: WrappedKey,
+}
+
+impl RecoveryBlob {
+    /// Wrap `key` for `project_id` under `passphrase`.
+    pub fn seal(
+        project_id: Uuid,
+        key: &[u8; 32],
+        passphrase: &str,
+        params: KdfParams,
+    ) -> Result<Self> {
+        Ok(RecoveryBlob {
+            project_id,
+            wrapped: WrappedKey::seal(key, passphrase, params)?,
+        })
+    }
+
+    /// Recover the master key.
+    pub fn open(&self, passphrase: &str) -> Result<[u8; 32]> {
+        self.wrapped.open(passphrase)
+    }
+
+    /// Armored text form; `paper` selects grouped hex instead of base64.
+    pub fn to_armored(&self, paper: bool) -> Result<String> {
+        let mut payload = vec![BLOB_VERSION];
+        payload.extend_from_slice(self.project_id.as_bytes());
+        payload.extend(self.wrapped.to_bytes()?);
+        let headers = [("Project", self.project_id.to_string())];
+        Ok(armor(RECOVERY_LABEL, &headers, &payload, paper))
+    }
+
+    /// Parse an armored blob (either encoding).
+    pub fn from_armored(text: &str) -> Result<Self> {
+        let payload = dearmor(RECOVERY_LABEL, text)?;
+        if payload.len() < 17 || payload[0] != BLOB_VERSION {
+            bail!("Unsupported or truncated recovery key");
+        }
+        let project_id = Uuid::from_slice(&payload[1..17])?;
+        Ok(RecoveryBlob {
+            project_id,
+            wrapped: WrappedKey::from_bytes(&payload[17..])?,
+        })
+    }
+}
+
+/// One Shamir share of a project's master key.
+#[derive(Clone)]
+pub struct KeyShare {
+    pub project_id: Uuid,
+    pub threshold: u8,
+    pub total: u8,
+    share: Share,
+}
+
+impl KeyShare {
+    /// 1-based index of this share.
+    pub fn index(&self) -> u8 {
+        self.share.x.0
+    }
+
+    /// Armored text form; `paper` selects grouped hex instead of base64.
+    pub fn to_armored(&self, paper: bool) -> String {
+        let mut payload = vec![BLOB_VERSION];
+        payload.extend_from_slice(self.project_id.as_bytes());
+        payload.push(self.threshold);
+        payload.push(self.total);
+        payload.extend(Vec::from(&self.share));
+        let headers = [
+            ("Project", self.project_id.to_string()),
+            ("Share", format!("{} of {}", self.index(), self.total)),
+            ("Threshold", self.threshold.to_string()),
+        ];
+        let text = armor(SHARE_LABEL, &headers, &payload, paper);
+        payload.zeroize();
+        text
+    }
+
+    /// Parse every share block found in `text`.
+    pub fn parse_all(text: &str) -> Result<Vec<Self>> {
+        let begin = format!("-----BEGIN {}-----", SHARE_LABEL);
+        let blocks: Vec<&str> = text.split(&begin).skip(1).collect();
+        if blocks.is_empty() {
+            bail!("No key shares found");
+        }
+        blocks
+            .into_iter()
+            .map(|block| {
+                let mut payload = dearmor(SHARE_LABEL, &format!("{}{}", begin, block))?;
+                let share = Self::from_payload(&payload);
+                payload.zeroize();
+                share
+            })
+            .collect()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self> {
+        if payload.len() <= SHARE_HEADER_LEN + 1 || payload[0] != BLOB_VERSION {
+            bail!("Unsupported or truncated key share");
+        }
+        Ok(KeyShare {
+            project_id: Uuid::from_slice(&payload[1..17])?,
+            threshold: payload[17],
+            total: payload[18],
+            share: Share::try_from(&payload[SHARE_HEADER_LEN..]).map_err(|e| anyhow!(e))?,
+        })
+    }
+}
+
+/// Split `key` into `total` shares, any `threshold` of which recover it.
+pub fn split_key(
+    project_id: Uuid,
+    key: &[u8; 32],
+    total: u8,
+    threshold: u8,
+) -> Result<Vec<KeyShare>> {
+    if threshold < 2 {
+        bail!("Threshold must be at least 2");
+    }
+    if threshold > total {
+        bail!(
+            "Threshold ({}) cannot exceed the number of shares ({})",
+            threshold,
+            total
+        );
+    }
+    Ok(Sharks(threshold)
+        .dealer(key)
+        .take(total as usize)
+        .map(|share| KeyShare {
+            project_id,
+            threshold,
+            total,
+            share,
+        })
+        .collect())
+}
+
+/// Recombine shares into the master key, refusing shares from different projects or splits.
+pub fn combine_shares(shares: &[KeyShare]) -> Result<(Uuid, [u8; 32])> {
+    let first = shares
+        .first()
+        .ok_or_else(|| anyhow!("No key shares given"))?;
+    if let Some(other) = shares.iter().find(|s| s.project_id != first.project_id) {
+        bail!(
+            "Shares belong to different projects ({} and {})",
+            first.project_id,
+            other.project_id
+        );
+    }
+    if shares
+        .iter()
+        .any(|s| s.threshold != first.threshold || s.total != first.total)
+    {
+        bail!("Shares come from different splits of the key");
+    }
+    let mut distinct: Vec<u8> = shares.iter().map(KeyShare::index).collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() < first.threshold as usize {
+        bail!(
+            "Need {} distinct shares, got {}",
+            first.threshold,
+            distinct.len()
+        );
+    }
+    let mut secret = Sharks(first.threshold)
+        .recover(shares.iter().map(|s| &s.share))
+        .map_err(|e| anyhow!("Failed to combine shares: {}", e))?;
+    if secret.len() != 32 {
+        secret.zeroize();
+        bail!("Recovered key has the wrong length");
+    }
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&secret);
+    secret.zeroize();
+    Ok((first.project_id, key))
+}
+
+/// Wrap `payload` (plus a checksum) in `-----BEGIN <label>-----` markers.
+pub fn armor(label: &str, headers: &[(&str, String)], payload: &[u8], paper: bool) -> String {
+    let mut data = payload.to_vec();
+    data.extend_from_slice(&checksum(payload));
+
+    let mut out = format!("-----BEGIN {}-----\n", label);
+    for (name, value) in headers {
+        out.push_str(&format!("{}: {}\n", name, value));
+    }
+    out.push_str(&format!(
+        "Encoding: {}\n\n",
+        if paper { "hex" } else { "base64" }
+    ));
+    if paper {
+        let hex: String = data.iter().map(|b| format!("{:02X}", b)).collect();
+        let groups: Vec<&str> = hex
+            .as_bytes()
+            .chunks(4)
+            .map(|c| std::str::from_utf8(c).unwrap_or_default())
+            .collect();
+        for line in groups.chunks(8) {
+            out.push_str(&line.join(" "));
+            out.push('\n');
+        }
+    } else {
+        let b64 = BASE64.encode(&data);
+        for line in b64.as_bytes().chunks(64) {
+            out.push_str(std::str::from_utf8(line).unwrap_or_default());
+            out.push('\n');
+        }
+    }
+    out.push_str(&format!("-----END {}-----\n", label));
+    out
+}
+
+/// Extract and checksum-verify the payload of an armored block.
+pub fn dearmor(label: &str, text: &str) -> Result<Vec<u8>> {
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+    let mut lines = text.lines().map(str::trim);
+    lines
+        .by_ref()
+        .find(|l| *l == begin)
+        .ok_or_else(|| anyhow!("Missing '{}' marker", begin))?;
+
+    let mut hex = false;
+    let mut body = String::new();
+    let mut closed = false;
+    for line in lines {
+        if line == end {
+            closed = true;
+            break;
+        }
+        if let Some((name, value)) = line.split_once(": ") {
+            if name.eq_ignore_ascii_case("Encoding") {
+                hex = value.trim().eq_ignore_ascii_case("hex");
+            }
+            continue;
+        }
+        body.extend(line.chars().filter(|c| !c.is_whitespace()));
+    }
+    if !closed {
+        bail!("Missing '{}' marker", end);
+    }
+
+    let data = if hex {
+        decode_hex(&body)?
+    } else {
+        BASE64
+            .decode(&body)
+            .context("Invalid base64 in armored block")?
+    };
+    if data.len() < CHECKSUM_LEN {
+        bail!("Armored block is truncated");
+    }
+    let (payload, sum) = data.split_at(data.len() - CHECKSUM_LEN);
+    if checksum(payload) != sum {
+        bail!("Checksum mismatch; the block was copied incorrectly");
+    }
+    Ok(payload.to_vec())
+}
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest = Sha256::digest(payload);
+    let mut out = [0u8; CHECKSUM_LEN];
+    out.copy_from_slice(&digest[..CHECKSUM_LEN]);
+    out
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    if let Some((i, c)) = s.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        bail!("Invalid hex digit '{}' near position {}", c, i);
+    }
+    if !s.len().is_multiple_of(2) {
+        bail!("Hex block has an odd number of digits");
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&s[i..i + 2], 16)
+                .map_err(|_| anyhow!("Invalid hex digit near position {}", i))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_armor_round_trip_both_encodings() {
+        let payload = b"hello recovery".to_vec();
+        for paper in [false, true] {
+            let text = armor("TEST BLOCK", &[("Project", "x".into())], &payload, paper);
+            assert!(text.starts_with("-----BEGIN TEST BLOCK-----\nProject: x\n"));
+            assert_eq!(dearmor("TEST BLOCK", &text).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn test_split_and_combine_with_threshold() {
+        let key = [42u8; 32];
+        let project = Uuid::new_v4();
+        let shares = split_key(project, &key, 5, 3).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        let text: String = [&shares[0], &shares[2], &shares[4]]
+            .iter()
+            .map(|s| s.to_armored(true))
+            .collect();
+        let parsed = KeyShare::parse_all(&text).unwrap();
+        assert_eq!(combine_shares(&parsed).unwrap(), (project, key));
+
+        assert!(combine_shares(&shares[..2]).is_err());
+        // duplicates don't count towards the threshold
+        let dupes = vec![shares[0].clone(), shares[0].clone(), shares[1].clone()];
+        assert!(combine_shares(&dupes).is_err());
+
+        let foreign = split_key(Uuid::new_v4(), &key, 5, 3).unwrap();
+        let mixed = vec![shares[0].clone(), shares[1].clone(), foreign[2].clone()];
+        let err = combine_shares(&mixed).unwrap_err();
+        assert!(err.to_string().contains("different projects"));
+
+        assert!(split_key(project, &key, 2, 3).is_err());
+        assert!(split_key(project, &key, 5, 1).is_err());
+    }
+
+    #[test]
+    fn test_combine_recovers_blahaj_shares() {
+        let key: [u8; 32] = std::array::from_fn(|i| i as u8 * 7);
+        let project = Uuid::new_v4();
+        let shares: Vec<KeyShare> = Sharks(3)
+            .dealer(&key)
+            .take(4)
+            .map(|share| KeyShare {
+                project_id: project,
+                threshold: 3,
+                total: 4,
+                share,
+            })
+            .collect();
+
+        for picked in [[0, 1, 2], [1, 2, 3], [3, 0, 2]] {
+            let text: String = picked
+                .iter()
+                .map(|&i| shares[i].to_armored(false))
+                .collect();
+            let parsed = KeyShare::parse_all(&text).unwrap();
+            assert_eq!(combine_shares(&parsed).unwrap(), (project, key));
+        }
+        assert!(combine_shares(&shares[1..3]).is_err());
+    }
+
+    #[test]
+    fn test_dearmor_detects_typos() {
+        let text = armor("TEST BLOCK", &[], b"paper backup", true);
+        let body_line = text.lines().nth(3).unwrap().to_string();
+        let first = body_line.chars().next().unwrap();
+        let typo = if first == '0' { '1' } else { '0' };
+        let tampered = text.replacen(&body_line, &format!("{}{}", typo, &body_line[1..]), 1);
+        let err = dearmor("TEST BLOCK", &tampered).unwrap_err();
+        assert!(err.to_string().contains("Checksum"));
+        assert!(dearmor("OTHER", &text).is_err());
+    }
+
+    #[test]
+    fn test_decode_hex_rejects_non_hex_input() {
+        assert_eq!(decode_hex("00ff7A").unwrap(), vec![0x00, 0xff, 0x7a]);
+        assert!(decode_hex("+f").is_err());
+        assert!(decode_hex("0é").is_err());
+        assert!(decode_hex("é0").is_err());
+        assert!(decode_hex("abc").is_err());
+
+        let text = armor("TEST BLOCK", &[], b"paper backup", true);
+        let pasted = text.replacen("\n", "\n\u{2014}", 3);
+        assert!(dearmor("TEST BLOCK", &pasted).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Template rendering for `cred render`.
+//!
+//! Placeholders look like `{{ cred "KEY" }}`, optionally piped through filters:
+//! `{{ cred "TLS_CERT" | indent 4 }}`, `{{ cred "TOKEN" | base64 }}`. Any other `{{ ... }}`
+//! is copied through untouched, so Helm or Compose templates can be pre-rendered safely.
+//! Every missing key and malformed placeholder is reported before anything is written.
+
+use anyhow::{Result, bail};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
+/// A rendered template and the keys it referenced (sorted, deduplicated).
+pub struct Rendered {
+    pub text: String,
+    pub keys: Vec<String>,
+}
+
+/// Filters available after `|`.
+#[derive(Debug, PartialEq)]
+enum Filter {
+    /// Standard base64 of the value.
+    Base64,
+    /// Escape for use inside a JSON string (no surrounding quotes).
+    JsonEscape,
+    /// A double-quoted YAML scalar.
+    YamlQuote,
+    /// Indent every line after the first by N spaces (PEM blocks in YAML).
+    Indent(usize),
+}
+
+impl Filter {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.split_whitespace();
+        let name = parts.next().unwrap_or_default();
+        let filter = match name {
+            "base64" => Filter::Base64,
+            "json-escape" => Filter::JsonEscape,
+            "yaml-quote" => Filter::YamlQuote,
+            "indent" => {
+                let n = parts
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(|| "indent needs a number of spaces".to_string())?;
+                Filter::Indent(n)
+            }
+            "" => return Err("empty filter".to_string()),
+            other => return Err(format!("unknown filter '{}'", other)),
+        };
+        if parts.next().is_some() {
+            return Err(format!("unexpected argument to '{}'", name));
+        }
+        Ok(filter)
+    }
+
+    fn apply(&self, value: String) -> String {
+        match self {
+            Filter::Base64 => BASE64.encode(value.as_bytes()),
+            Filter::JsonEscape => json_escape(&value),
+            Filter::YamlQuote => format!("\"{}\"", json_escape(&value)),
+            Filter::Indent(n) => value.replace('\n', &format!("\n{}", " ".repeat(*n))),
+        }
+    }
+}
+
+/// JSON string escaping; the result is also valid inside a YAML double-quoted scalar.
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// A `{{ cred "KEY" | filters }}` body (the text between the braces).
+struct Placeholder {
+    key: String,
+    filters: Vec<Filter>,
+}
+
+/// Parse a placeholder body, or `None` if it isn't a `cred` placeholder at all.
+fn parse_placeholder(body: &str) -> Option<Result<Placeholder, String>> {
+    let rest = body.trim().strip_prefix("cred")?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let rest = rest.trim_start();
+    Some((|| {
+        let rest = rest
+            .strip_prefix('"')
+            .ok_or_else(|| "expected a quoted key, e.g. cred \"KEY\"".to_string())?;
+        let (key, rest) = rest
+            .split_once('"')
+            .ok_or_else(|| "unterminated key".to_string())?;
+        if key.is_empty() {
+            return Err("empty key".to_string());
+        }
+        let rest = rest.trim();
+        let filters = if rest.is_empty() {
+            Vec::new()
+        } else {
+            rest.strip_prefix('|')
+                .ok_or_else(|| format!("unexpected '{}' after key", rest))?
+                .split('|')
+                .map(Filter::parse)
+                .collect::<Result<_, _>>()?
+        };
+        Ok(Placeholder {
+            key: key.to_string(),
+            filters,
+        })
+    })())
+}
+
+/// Render `template`, resolving keys with `lookup`.
+pub fn render<'a>(template: &str, lookup: impl Fn(&str) -> Option<&'a str>) -> Result<Rendered> {
+    let mut text = String::with_capacity(template.len());
+    let mut keys = Vec::new();
+    let mut problems = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let line = template[..template.len() - rest.len() + start]
+            .matches('\n')
+            .count()
+            + 1;
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            text.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let body = &after[..end];
+        match parse_placeholder(body) {
+            None => text.push_str(&rest[start..start + 2 + end + 2]),
+            Some(Err(msg)) => problems.push(format!("line {}: {}", line, msg)),
+            Some(Ok(placeholder)) => match lookup(&placeholder.key) {
+                Some(value) => {
+                    let value = placeholder
+                        .filters
+                        .iter()
+                        .fold(value.to_string(), |v, f| f.apply(v));
+                    text.push_str(&value);
+                    keys.push(placeholder.key);
+                }
+                None => problems.push(format!(
+                    "line {}: secret '{}' not found",
+                    line, placeholder.key
+                )),
+            },
+        }
+        rest = &after[end + 2..];
+    }
+    text.push_str(rest);
+
+    if !problems.is_empty() {
+        bail!("Template has errors:\n  {}", problems.join("\n  "));
+    }
+    keys.sort();
+    keys.dedup();
+    Ok(Rendered { text, keys })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(key: &str) -> Option<&'static str> {
+        match key {
+            "DB" => Some("postgres://u:p@h/db"),
+            "QUOTE" => Some("say \"hi\"\n"),
+            "CERT" => Some("-----BEGIN CERT-----\nMIIB\n-----END CERT-----"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_render_substitutes_and_filters() {
+        let tmpl = "url: {{ cred \"DB\" }}\n\
+                    b64: {{cred \"DB\"|base64}}\n\
+                    json: \"{{ cred \"QUOTE\" | json-escape }}\"\n\
+                    yaml: {{ cred \"QUOTE\" | yaml-quote }}\n\
+                    cert: |\n  {{ cred \"CERT\" | indent 2 }}\n";
+        let out = render(tmpl, lookup).unwrap();
+        assert_eq!(
+            out.text,
+            "url: postgres://u:p@h/db\n\
+             b64: cG9zdGdyZXM6Ly91OnBAaC9kYg==\n\
+             json: \"say \\\"hi\\\"\\n\"\n\
+             yaml: \"say \\\"hi\\\"\\n\"\n\
+             cert: |\n  -----BEGIN CERT-----\n  MIIB\n  -----END CERT-----\n"
+        );
+        assert_eq!(out.keys, vec!["CERT", "DB", "QUOTE"]);
+    }
+
+    #[test]
+    fn test_render_leaves_other_templates_alone() {
+        let tmpl = "image: {{ .Values.image }}\ncredentials: {{ credentials }}\nopen {{ cred";
+        assert_eq!(render(tmpl, lookup).unwrap().text, tmpl);
+    }
+
+    #[test]
+    fn test_render_reports_every_problem() {
+        let tmpl = "a: {{ cred \"MISSING\" }}\nb: {{ cred \"DB\" | rot13 }}\nc: {{ cred DB }}\n";
+        let err = render(tmpl, lookup).err().unwrap().to_string();
+        assert!(
+            err.contains("line 1: secret 'MISSING' not found"),
+            "{}",
+            err
+        );
+        assert!(err.contains("line 2: unknown filter 'rot13'"), "{}", err);
+        assert!(err.contains("line 3: expected a quoted key"), "{}", err);
+    }
+}
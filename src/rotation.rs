@@ -0,0 +1,160 @@
+//! Scheduled rotation for `cred rotate`.
+//!
+//! A secret can carry a `RotationPolicy` in its metadata: a maximum age and the generator
+//! recipe that replaces it. An entry is overdue once its `updated_at` is older than the
+//! maximum age, so setting a value by hand also restarts the clock.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::generate::{self, Recipe};
+use crate::vault::Vault;
+
+/// How often a secret is regenerated, and how.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationPolicy {
+    pub max_age_days: u32,
+    pub recipe: Recipe,
+}
+
+impl RotationPolicy {
+    /// When a value last written at `updated_at` becomes overdue. A policy that would land
+    /// past the last representable date is never due.
+    pub fn due_at(&self, updated_at: DateTime<Utc>) -> DateTime<Utc> {
+        updated_at
+            .checked_add_signed(Duration::days(i64::from(self.max_age_days)))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+
+    pub fn is_due(&self, updated_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.due_at(updated_at) <= now
+    }
+}
+
+/// Longest accepted duration: 100 years.
+pub const MAX_DAYS: u32 = 100 * 365;
+
+/// Parse a number of days written as `90d`, `12w`, `1y` (365 days) or a bare `90`,
+/// up to `MAX_DAYS`.
+pub fn parse_days(input: &str) -> Result<u32> {
+    let s = input.trim();
+    let (digits, per_unit) = match s.char_indices().last() {
+        Some((i, 'd')) => (&s[..i], 1),
+        Some((i, 'w')) => (&s[..i], 7),
+        Some((i, 'y')) => (&s[..i], 365),
+        _ => (s, 1),
+    };
+    let n: u32 = digits
+        .parse()
+        .with_context(|| format!("Invalid duration '{}'; use e.g. 90d, 12w or 1y", input))?;
+    if n == 0 {
+        bail!("Duration '{}' must be at least one day", input);
+    }
+    match n.checked_mul(per_unit) {
+        Some(days) if days <= MAX_DAYS => Ok(days),
+        _ => bail!("Duration '{}' is too long; the maximum is 100y", input),
+    }
+}
+
+/// Keys in this vault (not inherited ones) whose rotation policy is overdue, sorted.
+pub fn due_keys(vault: &Vault, now: DateTime<Utc>) -> Vec<String> {
+    let mut keys: Vec<String> = vault
+        .list_entries()
+        .iter()
+        .filter(|(_, e)| e.rotation_due(now))
+        .map(|(k, _)| k.clone())
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// A secret replaced by `rotate`.
+pub struct Rotated {
+    pub key: String,
+    pub version: u32,
+    /// `<KEY>_PUBLIC`, when the recipe makes a key pair.
+    pub public_key: Option<String>,
+}
+
+/// Regenerate `key` from its policy's recipe. The old value moves to history; the
+/// description and policy are kept, and a key pair's `<KEY>_PUBLIC` is replaced too.
+pub fn rotate(vault: &mut Vault, key: &str) -> Result<Rotated> {
+    if vault.is_inherited(key) {
+        bail!(
+            "'{}' is inherited; rotate it in the environment that defines it",
+            key
+        );
+    }
+    let entry = vault
+        .list_entries()
+        .get(key)
+        .with_context(|| format!("Secret '{}' not found", key))?;
+    let policy = entry.rotation.clone().with_context(|| {
+        format!(
+            "'{}' has no rotation policy; set one with `cred secret generate {} --force --rotate-every 90d`",
+            key, key
+        )
+    })?;
+    let description = entry.description.clone();
+
+    let generated = policy.recipe.generate()?;
+    vault.set_with_metadata(key, &generated.value, generated.format.clone(), description);
+    let public_key = match &generated.public {
+        Some(public) => {
+            let public_key = format!("{}{}", key, generate::PUBLIC_KEY_SUFFIX);
+            vault.set_with_metadata(
+                &public_key,
+                public,
+                Vault::detect_format(public),
+                Some(format!("Public key for {}", key)),
+            );
+            Some(public_key)
+        }
+        None => None,
+    };
+    let version = vault.get_entry(key).map(|e| e.version).unwrap_or(1);
+    Ok(Rotated {
+        key: key.to_string(),
+        version,
+        public_key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_days_units() {
+        assert_eq!(parse_days("90d").unwrap(), 90);
+        assert_eq!(parse_days("90").unwrap(), 90);
+        assert_eq!(parse_days("2w").unwrap(), 14);
+        assert_eq!(parse_days(" 1y ").unwrap(), 365);
+        assert_eq!(parse_days("100y").unwrap(), MAX_DAYS);
+        for bad in [
+            "", "0d", "d", "-3d", "10h", "1.5d", "101y", "999999y", "36501",
+        ] {
+            assert!(parse_days(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_policy_is_due_after_max_age() {
+        let policy = RotationPolicy {
+            max_age_days: 30,
+            recipe: Recipe::Uuid,
+        };
+        let now = Utc::now();
+        assert!(!policy.is_due(now - Duration::days(29), now));
+        assert!(policy.is_due(now - Duration::days(30), now));
+
+        // Policies stored before the cap must not overflow.
+        let policy = RotationPolicy {
+            max_age_days: u32::MAX,
+            recipe: Recipe::Uuid,
+        };
+        assert_eq!(policy.due_at(now), DateTime::<Utc>::MAX_UTC);
+        assert!(!policy.is_due(now, now));
+    }
+}
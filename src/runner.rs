@@ -0,0 +1,226 @@
+//! `cred run`: spawn a command with vault secrets in its environment.
+//!
+//! Values are decrypted into memory and handed to the child only; nothing is written to
+//! disk unless `--files` asks for PEM/multiline entries as owner-only temp files, which are
+//! removed when the command exits.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::PathBuf;
+use tokio::process::Command;
+use zeroize::Zeroize;
+
+use crate::fsutil;
+use crate::shell;
+use crate::vault::{SecretFormat, Vault};
+
+/// Suffix of the variable pointing at a materialised secret file (`TLS_CERT_FILE`).
+pub const FILE_VAR_SUFFIX: &str = "_FILE";
+
+/// Environment prepared for the child process; zeroized and cleaned up on drop.
+#[derive(Default)]
+pub struct RunEnv {
+    vars: Vec<(String, String)>,
+    files_dir: Option<PathBuf>,
+}
+
+impl RunEnv {
+    /// Names of the variables that will be set, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.vars.iter().map(|(k, _)| k.as_str()).collect();
+        names.sort();
+        names
+    }
+}
+
+impl Drop for RunEnv {
+    fn drop(&mut self) {
+        for (k, v) in self.vars.iter_mut() {
+            k.zeroize();
+            v.zeroize();
+        }
+        if let Some(dir) = self.files_dir.take() {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Collect secrets (all, or just `keys`) from `vault`, inherited values included.
+/// With `files`, PEM and multiline values are also written to temp files exposed as
+/// `<KEY>_FILE`.
+pub fn collect_env(vault: &Vault, keys: Option<&[String]>, files: bool) -> Result<RunEnv> {
+    let mut env = RunEnv::default();
+    for (key, entry) in vault.select_entries(keys)? {
+        if key.is_empty() || key.contains('=') || key.contains('\0') {
+            bail!("'{}' is not a valid environment variable name", key);
+        }
+        env.vars.push((key.clone(), entry.value.clone()));
+        if files && matches!(entry.format, SecretFormat::Pem | SecretFormat::Multiline) {
+            // The key becomes a file name, so anything like `../x` must not reach `join`.
+            if !shell::is_valid_name(key) {
+                bail!(
+                    "'{}' cannot be written with --files; only names matching [A-Za-z_][A-Za-z0-9_]* can",
+                    key
+                );
+            }
+            let dir = match &env.files_dir {
+                Some(dir) => dir.clone(),
+                None => {
+                    let dir = std::env::temp_dir()
+                        .join(format!("cred-run-{}", uuid::Uuid::new_v4().simple()));
+                    fsutil::create_private_dir(&dir)?;
+                    env.files_dir = Some(dir.clone());
+                    dir
+                }
+            };
+            let path = dir.join(key);
+            fsutil::write_atomic(&path, entry.value.as_bytes())?;
+            env.vars.push((
+                format!("{}{}", key, FILE_VAR_SUFFIX),
+                path.to_string_lossy().to_string(),
+            ));
+        }
+    }
+    Ok(env)
+}
+
+/// Run `command` with `env` added to the inherited environment and return its exit code
+/// (`128 + signal` if it was killed). SIGTERM, SIGHUP, SIGUSR1 and SIGUSR2 sent to cred are
+/// forwarded; SIGINT/SIGQUIT from the terminal already reach the whole foreground group.
+pub async fn run_command(command: &[String], env: &RunEnv) -> Result<i32> {
+    let (program, args) = command.split_first().context("No command given")?;
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(env.vars.iter().map(|(k, v)| (k, v)))
+        .spawn()
+        .with_context(|| format!("Failed to run '{}'", program))?;
+
+    #[cfg(unix)]
+    let status = {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut term = signal(SignalKind::terminate())?;
+        let mut hup = signal(SignalKind::hangup())?;
+        let mut usr1 = signal(SignalKind::user_defined1())?;
+        let mut usr2 = signal(SignalKind::user_defined2())?;
+        let mut int = signal(SignalKind::interrupt())?;
+        let mut quit = signal(SignalKind::quit())?;
+        let pid = child.id();
+        let forward = |sig: libc::c_int| {
+            if let Some(pid) = pid {
+                // SAFETY: kill(2) with a pid we spawned and still wait on; no memory involved.
+                unsafe {
+                    libc::kill(pid as libc::pid_t, sig);
+                }
+            }
+        };
+        loop {
+            tokio::select! {
+                status = child.wait() => break status?,
+                _ = term.recv() => forward(libc::SIGTERM),
+                _ = hup.recv() => forward(libc::SIGHUP),
+                _ = usr1.recv() => forward(libc::SIGUSR1),
+                _ = usr2.recv() => forward(libc::SIGUSR2),
+                _ = int.recv() => {}
+                _ = quit.recv() => {}
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let status = child.wait().await?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(sig) = status.signal() {
+            return Ok(128 + sig);
+        }
+    }
+    Ok(status.code().unwrap_or(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(dir: &std::path::Path) -> Vault {
+        let mut v = Vault::load(&dir.join("vault.enc"), [3u8; 32]).unwrap();
+        v.set("API_KEY", "abc");
+        v.set(
+            "TLS_CERT",
+            "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n",
+        );
+        v
+    }
+
+    #[test]
+    fn test_collect_env_selects_keys_and_mounts_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let v = vault(dir.path());
+
+        let all = collect_env(&v, None, false).unwrap();
+        assert_eq!(all.names(), vec!["API_KEY", "TLS_CERT"]);
+
+        let only = collect_env(&v, Some(&["API_KEY".to_string()]), false).unwrap();
+        assert_eq!(only.names(), vec!["API_KEY"]);
+        assert!(collect_env(&v, Some(&["MISSING".to_string()]), false).is_err());
+
+        let with_files = collect_env(&v, None, true).unwrap();
+        assert_eq!(
+            with_files.names(),
+            vec!["API_KEY", "TLS_CERT", "TLS_CERT_FILE"]
+        );
+        let path = PathBuf::from(
+            &with_files
+                .vars
+                .iter()
+                .find(|(k, _)| k == "TLS_CERT_FILE")
+                .unwrap()
+                .1,
+        );
+        assert!(fs::read_to_string(&path).unwrap().contains("MIIB"));
+        assert!(fsutil::is_private(&path));
+        drop(with_files);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_collect_env_files_rejects_path_like_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut v = vault(dir.path());
+        for key in ["../escape", "nested/KEY", "back\\slash", "..", "DOT.NAME"] {
+            v.set(key, "line one\nline two\n");
+            let err = collect_env(&v, Some(&[key.to_string()]), true)
+                .err()
+                .unwrap_or_else(|| panic!("{key} should be rejected"));
+            assert!(err.to_string().contains("--files"), "{err}");
+            // Without --files the value only travels in the environment.
+            assert!(collect_env(&v, Some(&[key.to_string()]), false).is_ok());
+        }
+        assert!(!dir.path().join("escape").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_command_passes_env_and_exit_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = collect_env(&vault(dir.path()), None, false).unwrap();
+        let script = |s: &str| vec!["sh".to_string(), "-c".to_string(), s.to_string()];
+
+        assert_eq!(
+            run_command(&script("test \"$API_KEY\" = abc"), &env)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(run_command(&script("exit 7"), &env).await.unwrap(), 7);
+        assert_eq!(
+            run_command(&script("kill -TERM $$"), &env).await.unwrap(),
+            143
+        );
+        assert!(
+            run_command(&["/no/such/program".to_string()], &env)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Shell-specific `export` statements for `cred env`, safe to `eval`.
+//!
+//! Every value is emitted as a single-quoted (or raw) literal in the target dialect, so
+//! spaces, quotes, `$`, backticks and newlines are reproduced exactly and never expanded.
+
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+use std::path::Path;
+
+/// direnv stdlib extension defining `use cred` (printed by `cred direnv --stdlib`).
+pub const DIRENV_STDLIB: &str = include_str!("../scripts/use_cred.sh");
+
+/// direnv statement reloading the environment when `path` changes.
+pub fn direnv_watch(path: &Path) -> String {
+    format!("watch_file {}", posix_quote(&path.to_string_lossy()))
+}
+
+/// Supported shell dialects.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(name = "powershell", alias = "pwsh")]
+    Powershell,
+    #[value(name = "nushell", alias = "nu")]
+    Nushell,
+}
+
+impl Shell {
+    /// Guess the dialect from `$SHELL`, defaulting to bash.
+    pub fn detect() -> Self {
+        let shell = std::env::var("SHELL").unwrap_or_default();
+        let name = Path::new(&shell)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        match name {
+            "zsh" => Shell::Zsh,
+            "fish" => Shell::Fish,
+            "pwsh" | "powershell" => Shell::Powershell,
+            "nu" => Shell::Nushell,
+            _ => Shell::Bash,
+        }
+    }
+
+    /// One statement setting `key` to `value` in this dialect.
+    pub fn export(self, key: &str, value: &str) -> Result<String> {
+        if !is_valid_name(key) {
+            bail!(
+                "'{}' is not a valid environment variable name for shell export",
+                key
+            );
+        }
+        Ok(match self {
+            Shell::Bash | Shell::Zsh => format!("export {}={}", key, posix_quote(value)),
+            Shell::Fish => format!("set -gx {} {}", key, fish_quote(value)),
+            Shell::Powershell => format!("$env:{} = {}", key, powershell_quote(value)),
+            Shell::Nushell => format!("$env.{} = {}", key, nushell_quote(value)),
+        })
+    }
+}
+
+/// `[A-Za-z_][A-Za-z0-9_]*`, the names every supported shell accepts unquoted.
+pub(crate) fn is_valid_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `'...'` with each `'` written as `'\''`.
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Fish single quotes only interpret `\\` and `\'`.
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Powershell single quotes only interpret `''`.
+fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Nushell raw string `r#'...'#`, with enough `#`s that the value can't close it early.
+fn nushell_quote(value: &str) -> String {
+    let mut hashes = 1;
+    while value.contains(&format!("'{}", "#".repeat(hashes))) {
+        hashes += 1;
+    }
+    let fence = "#".repeat(hashes);
+    format!("r{}'{}'{}", fence, value, fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRICKY: &str = "it's $HOME `id` \"q\" \\ \nline2";
+
+    #[test]
+    fn test_export_quotes_each_dialect() {
+        assert_eq!(
+            Shell::Bash.export("K", TRICKY).unwrap(),
+            "export K='it'\\''s $HOME `id` \"q\" \\ \nline2'"
+        );
+        assert_eq!(
+            Shell::Fish.export("K", TRICKY).unwrap(),
+            "set -gx K 'it\\'s $HOME `id` \"q\" \\\\ \nline2'"
+        );
+        assert_eq!(
+            Shell::Powershell.export("K", TRICKY).unwrap(),
+            "$env:K = 'it''s $HOME `id` \"q\" \\ \nline2'"
+        );
+        assert_eq!(
+            Shell::Nushell.export("K", TRICKY).unwrap(),
+            "$env.K = r#'it's $HOME `id` \"q\" \\ \nline2'#"
+        );
+        assert_eq!(
+            Shell::Nushell.export("K", "a'#b").unwrap(),
+            "$env.K = r##'a'#b'##"
+        );
+    }
+
+    #[test]
+    fn test_direnv_watch_quotes_path() {
+        assert_eq!(
+            direnv_watch(Path::new("/my proj/.cred/vault.enc")),
+            "watch_file '/my proj/.cred/vault.enc'"
+        );
+        assert!(DIRENV_STDLIB.contains("use_cred()"));
+    }
+
+    #[test]
+    fn test_export_rejects_unsafe_names() {
+        assert!(Shell::Bash.export("OK_1", "v").is_ok());
+        for bad in ["1ABC", "A-B", "A B", "$(id)", ""] {
+            assert!(Shell::Bash.export(bad, "v").is_err(), "{}", bad);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_posix_output_round_trips_through_sh() {
+        let line = Shell::Bash.export("K", TRICKY).unwrap();
+        let out = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{}\nprintf %s \"$K\"", line))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(out.stdout).unwrap(), TRICKY);
+    }
+}
@@ -5,7 +5,7 @@
 use super::{PushOptions, TargetAdapter};
 use anyhow::{Context, Result};
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
-use reqwest::{Client, RequestBuilder};
+use reqwest::{Client, RequestBuilder, Url};
 use serde::Deserialize;
 use sodiumoxide::crypto::box_::curve25519xsalsa20poly1305::PublicKey;
 use sodiumoxide::crypto::sealedbox;
@@ -27,7 +27,7 @@ impl Github {
     const UA: &'static str = "cred-cli";
     const API_VERSION: &'static str = "2022-11-28";
 
-    fn with_headers<'a>(&self, builder: RequestBuilder, token: &'a str) -> RequestBuilder {
+    fn with_headers(&self, builder: RequestBuilder, token: &str) -> RequestBuilder {
         builder
             .header("User-Agent", Self::UA)
             .header("Authorization", format!("Bearer {}", token))
@@ -71,7 +71,7 @@ impl Github {
 
         let clean = remote.trim_end_matches(".git");
 
-        let parts: Vec<&str> = clean.split(|c| c == '/' || c == ':').collect();
+        let parts: Vec<&str> = clean.split(['/', ':']).collect();
         if parts.len() < 2 {
             anyhow::bail!("Invalid git remote format: {}", remote);
         }
@@ -82,6 +82,31 @@ impl Github {
         Ok(format!("{}/{}", owner, repo))
     }
 
+    /// Secrets API base for the repository, or for one of its deployment environments.
+    /// Environment names are free-form, so they are percent-encoded as a single path segment.
+    pub(super) fn secrets_api_base(repo: &str, environment: Option<&str>) -> String {
+        match environment {
+            Some(env) => {
+                let mut url = Url::parse("https://api.github.com").expect("static API URL");
+                url.path_segments_mut()
+                    .expect("https URLs have path segments")
+                    .push("repos")
+                    .extend(repo.split('/'))
+                    .extend(["environments", env, "secrets"]);
+                url.to_string()
+            }
+            None => format!("https://api.github.com/repos/{}/actions/secrets", repo),
+        }
+    }
+
+    /// Human-readable description of where secrets are written.
+    fn describe_target(repo: &str, environment: Option<&str>) -> String {
+        match environment {
+            Some(env) => format!("Repository: {}, Environment: {}", repo, env),
+            None => format!("Repository: {}", repo),
+        }
+    }
+
     /// Resolves a GitHub target from CLI options; kept for parity with other targets.
     async fn resolve_target(
         &self,
@@ -100,14 +125,15 @@ impl TargetAdapter for Github {
     }
 
     /// Pushes secrets to a repository by fetching its public key, encrypting each value,
-    /// and calling `PUT /repos/{owner}/{repo}/actions/secrets/{name}`.
+    /// and calling `PUT /repos/{owner}/{repo}/actions/secrets/{name}` (or the
+    /// `environments/{env}/secrets` equivalent when an environment is bound).
     async fn push(
         &self,
         secrets: &HashMap<String, String>,
         auth_token: &str,
-        _options: &PushOptions,
+        options: &PushOptions,
     ) -> Result<()> {
-        let repo_name = match &_options.repo {
+        let repo_name = match &options.repo {
             Some(r) => r.clone(),
             None => self.get_repo_from_git()?,
         };
@@ -115,8 +141,9 @@ impl TargetAdapter for Github {
         let client = Client::new();
         let target = self.resolve_target(&client, auth_token, &repo_name).await?;
 
-        let api_base = format!("https://api.github.com/repos/{}/actions/secrets", target.0);
-        let human_name = format!("Repository: {}", target.0);
+        let environment = options.environment.as_deref();
+        let api_base = Self::secrets_api_base(&target.0, environment);
+        let human_name = Self::describe_target(&target.0, environment);
 
         println!("🚀 Pushing to GitHub [{}]", human_name);
 
@@ -157,13 +184,8 @@ impl TargetAdapter for Github {
 
     /// Deletes secrets from a repository via `DELETE /repos/{owner}/{repo}/actions/secrets/{name}`.
     /// Treats 404s as no-op skips; other failures abort the operation.
-    async fn delete(
-        &self,
-        keys: &[String],
-        auth_token: &str,
-        _options: &PushOptions,
-    ) -> Result<()> {
-        let repo_name = match &_options.repo {
+    async fn delete(&self, keys: &[String], auth_token: &str, options: &PushOptions) -> Result<()> {
+        let repo_name = match &options.repo {
             Some(r) => r.clone(),
             None => self.get_repo_from_git()?,
         };
@@ -171,8 +193,9 @@ impl TargetAdapter for Github {
         let client = Client::new();
         let target = self.resolve_target(&client, auth_token, &repo_name).await?;
 
-        let api_base = format!("https://api.github.com/repos/{}/actions/secrets", target.0);
-        let human_name = format!("Repository: {}", target.0);
+        let environment = options.environment.as_deref();
+        let api_base = Self::secrets_api_base(&target.0, environment);
+        let human_name = Self::describe_target(&target.0, environment);
 
         println!(
             "🗑️  Pruning {} secrets from GitHub [{}]",
@@ -32,6 +32,8 @@ impl fmt::Display for Target {
 
 pub struct PushOptions {
     pub repo: Option<String>,
+    /// Remote environment to scope secrets to (e.g. a GitHub deployment environment).
+    pub environment: Option<String>,
 }
 
 #[allow(async_fn_in_trait)] // Async in trait is crate-internal; we accept the bound
@@ -171,6 +173,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_github_environment_is_percent_encoded() {
+        assert_eq!(
+            github::Github::secrets_api_base("org/repo", Some("prod/../x y")),
+            "https://api.github.com/repos/org/repo/environments/prod%2F..%2Fx%20y/secrets"
+        );
+        assert_eq!(
+            github::Github::secrets_api_base("org/repo", None),
+            "https://api.github.com/repos/org/repo/actions/secrets"
+        );
+    }
+
     #[test]
     fn test_factory_returns_github() {
         let p = get(Target::Github);
@@ -182,7 +196,10 @@ mod tests {
     async fn test_trait_defaults_prevent_invalid_usage() {
         let p = MockTarget;
         let secrets = HashMap::new();
-        let options = PushOptions { repo: None };
+        let options = PushOptions {
+            repo: None,
+            environment: None,
+        };
 
         let push_result = p.push(&secrets, "token", &options).await;
         assert!(push_result.is_err());
@@ -0,0 +1,284 @@
+//! Team sharing: the project key sealed for several X25519 recipients.
+//!
+//! `.cred/recipients` lists members' public keys (one `<public_key> <name>` per line) and
+//! `.cred/keys.wrapped` holds the project key sealed to each of them. Both files are safe
+//! to commit; members decrypt with their own private identity key, which never leaves
+//! their keystore.
+
+use anyhow::{Context, Result, anyhow, bail};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use sodiumoxide::crypto::box_::{self, PublicKey, SecretKey};
+use sodiumoxide::crypto::sealedbox;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroize;
+
+use crate::{config, fsutil};
+
+/// File listing member public keys under `.cred/`.
+pub const RECIPIENTS_FILE: &str = "recipients";
+
+/// File holding the project key sealed for each recipient under `.cred/`.
+pub const WRAPPED_KEYS_FILE: &str = "keys.wrapped";
+
+/// Env override for the private identity key (base64), for CI.
+pub const IDENTITY_ENV: &str = "CRED_IDENTITY_B64";
+
+const WRAPPED_KEYS_VERSION: u8 = 1;
+
+/// A member allowed to decrypt the project key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipient {
+    pub name: String,
+    /// Base64-encoded X25519 public key.
+    pub public_key: String,
+}
+
+/// This user's X25519 key pair.
+pub struct Identity {
+    public: PublicKey,
+    secret: SecretKey,
+}
+
+impl Identity {
+    /// Base64 public key to hand to teammates (`cred member add`).
+    pub fn public_b64(&self) -> String {
+        BASE64.encode(self.public.0)
+    }
+
+    /// A fresh random key pair (not stored anywhere).
+    pub fn generate() -> Result<Self> {
+        init_sodium()?;
+        let (public, secret) = box_::gen_keypair();
+        Ok(Identity { public, secret })
+    }
+
+    fn from_secret_b64(b64: &str) -> Result<Self> {
+        let mut raw = BASE64
+            .decode(b64.trim())
+            .context("Invalid base64 in identity key")?;
+        let secret = SecretKey::from_slice(&raw);
+        raw.zeroize();
+        let secret = secret.ok_or_else(|| anyhow!("Identity key must be 32 bytes"))?;
+        Ok(Identity {
+            public: secret.public_key(),
+            secret,
+        })
+    }
+}
+
+/// Load this user's identity from `CRED_IDENTITY_B64` or the keystore, if one exists.
+pub fn load_identity() -> Result<Option<Identity>> {
+    init_sodium()?;
+    if let Ok(b64) = std::env::var(IDENTITY_ENV) {
+        return Identity::from_secret_b64(&b64).map(Some);
+    }
+    match config::get_identity_key()? {
+        Some(mut b64) if !b64.is_empty() => {
+            let identity = Identity::from_secret_b64(&b64);
+            b64.zeroize();
+            identity.map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Load this user's identity, generating and storing a new one on first use.
+pub fn load_or_create_identity() -> Result<Identity> {
+    if let Some(identity) = load_identity()? {
+        return Ok(identity);
+    }
+    let identity = Identity::generate()?;
+    let mut b64 = BASE64.encode(identity.secret.0);
+    let stored = config::set_identity_key(&b64);
+    b64.zeroize();
+    stored.context("Failed to store identity key")?;
+    Ok(identity)
+}
+
+/// Decode and validate a base64 X25519 public key.
+pub fn parse_public_key(b64: &str) -> Result<PublicKey> {
+    let raw = BASE64
+        .decode(b64.trim())
+        .with_context(|| format!("Invalid base64 public key '{}'", b64))?;
+    PublicKey::from_slice(&raw).ok_or_else(|| anyhow!("Public key must be 32 bytes"))
+}
+
+/// Read `.cred/recipients` (missing file → no recipients). `#` starts a comment.
+pub fn read_recipients(path: &Path) -> Result<Vec<Recipient>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).context("Failed to read recipients")?;
+    parse_recipients(&content)
+}
+
+/// Parse recipients file content.
+pub fn parse_recipients(content: &str) -> Result<Vec<Recipient>> {
+    let mut recipients = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, name) = line.split_once(char::is_whitespace).ok_or_else(|| {
+            anyhow!(
+                "recipients line {}: expected '<public_key> <name>'",
+                idx + 1
+            )
+        })?;
+        let _ = parse_public_key(key).with_context(|| format!("recipients line {}", idx + 1))?;
+        recipients.push(Recipient {
+            name: name.trim().to_string(),
+            public_key: key.to_string(),
+        });
+    }
+    Ok(recipients)
+}
+
+/// Write `.cred/recipients`.
+pub fn write_recipients(path: &Path, recipients: &[Recipient]) -> Result<()> {
+    let mut out = String::from("# cred team recipients: <public_key> <name>\n");
+    for r in recipients {
+        out.push_str(&format!("{} {}\n", r.public_key, r.name));
+    }
+    fsutil::write_atomic(path, out.as_bytes()).context("Failed to write recipients")
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SealedFor {
+    recipient: String,
+    sealed_key: String,
+}
+
+/// On-disk `.cred/keys.wrapped`: the project key sealed for each recipient.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WrappedKeys {
+    version: u8,
+    keys: Vec<SealedFor>,
+}
+
+impl WrappedKeys {
+    /// Seal `key` for every recipient.
+    pub fn seal(key: &[u8; 32], recipients: &[Recipient]) -> Result<Self> {
+        init_sodium()?;
+        if recipients.is_empty() {
+            bail!("At least one recipient is required");
+        }
+        let keys = recipients
+            .iter()
+            .map(|r| {
+                let pk = parse_public_key(&r.public_key)?;
+                Ok(SealedFor {
+                    recipient: r.public_key.clone(),
+                    sealed_key: BASE64.encode(sealedbox::seal(key, &pk)),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(WrappedKeys {
+            version: WRAPPED_KEYS_VERSION,
+            keys,
+        })
+    }
+
+    /// Open the copy sealed for `identity`.
+    pub fn open(&self, identity: &Identity) -> Result<[u8; 32]> {
+        if self.version != WRAPPED_KEYS_VERSION {
+            bail!(
+                "Unsupported wrapped keys version: {}. Please upgrade cred.",
+                self.version
+            );
+        }
+        let me = identity.public_b64();
+        let sealed = self
+            .keys
+            .iter()
+            .find(|k| k.recipient == me)
+            .ok_or_else(|| {
+                anyhow!(
+                    "You are not a recipient of this vault. Share your public key ({}) with a member so they can run `cred member add`.",
+                    me
+                )
+            })?;
+        let ciphertext = BASE64
+            .decode(&sealed.sealed_key)
+            .context("Invalid sealed key base64")?;
+        let mut plaintext = sealedbox::open(&ciphertext, &identity.public, &identity.secret)
+            .map_err(|_| anyhow!("Failed to unseal project key with your identity"))?;
+        if plaintext.len() != 32 {
+            plaintext.zeroize();
+            bail!("Invalid key length in wrapped keys");
+        }
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&plaintext);
+        plaintext.zeroize();
+        Ok(key)
+    }
+
+    /// Read `.cred/keys.wrapped`.
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).context("Failed to read keys.wrapped")?;
+        serde_json::from_str(&content).context("Failed to parse keys.wrapped")
+    }
+
+    /// Write `.cred/keys.wrapped`.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fsutil::write_atomic(path, json.as_bytes()).context("Failed to write keys.wrapped")
+    }
+}
+
+fn init_sodium() -> Result<()> {
+    sodiumoxide::init().map_err(|_| anyhow!("Failed to initialize sodiumoxide"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity() -> Identity {
+        init_sodium().unwrap();
+        let (public, secret) = box_::gen_keypair();
+        Identity { public, secret }
+    }
+
+    #[test]
+    fn test_each_recipient_opens_their_copy() {
+        let alice = identity();
+        let bob = identity();
+        let mallory = identity();
+        let recipients = vec![
+            Recipient {
+                name: "alice".into(),
+                public_key: alice.public_b64(),
+            },
+            Recipient {
+                name: "bob".into(),
+                public_key: bob.public_b64(),
+            },
+        ];
+        let key = [7u8; 32];
+        let wrapped = WrappedKeys::seal(&key, &recipients).unwrap();
+        assert_eq!(wrapped.open(&alice).unwrap(), key);
+        assert_eq!(wrapped.open(&bob).unwrap(), key);
+        assert!(wrapped.open(&mallory).is_err());
+    }
+
+    #[test]
+    fn test_recipients_file_round_trip() {
+        let alice = identity();
+        let content = format!("# comment\n\n{} Alice Smith\n", alice.public_b64());
+        let parsed = parse_recipients(&content).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].name, "Alice Smith");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(RECIPIENTS_FILE);
+        write_recipients(&path, &parsed).unwrap();
+        assert_eq!(read_recipients(&path).unwrap(), parsed);
+
+        assert!(parse_recipients("not-base64 bob\n").is_err());
+        assert!(parse_recipients("lonely\n").is_err());
+    }
+}
@@ -57,11 +57,12 @@ async fn github_push_and_prune_round_trip() -> Result<()> {
     let gh = targets::get(targets::Target::Github).expect("github target");
     let opts = targets::PushOptions {
         repo: Some(full_repo.clone()),
+        environment: None,
     };
 
     gh.push(&secrets, &token, &opts).await?;
     assert_secret_exists(&client, &token, &full_repo, "E2E_ALPHA").await?;
-    gh.delete(&vec!["E2E_ALPHA".into(), "E2E_BETA".into()], &token, &opts)
+    gh.delete(&["E2E_ALPHA".into(), "E2E_BETA".into()], &token, &opts)
         .await?;
     assert_secret_absent(&client, &token, &full_repo, "E2E_ALPHA").await?;
     Ok(())
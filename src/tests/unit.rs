@@ -1,6 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use crate::{config, envfile, error, project, vault};
+    use crate::{
+        config, envfile, error, expiry, formats, fsutil, generate, keyfile, project, recovery,
+        rotation, vault,
+    };
+    use formats::FileFormat;
     use rand::RngCore;
     use std::fs;
     use std::process::Command;
@@ -43,7 +47,6 @@ mod tests {
 
         let entry = "\n.cred/\n";
         let mut file = fs::OpenOptions::new()
-            .write(true)
             .append(true)
             .open(&gitignore)
             .unwrap();
@@ -113,6 +116,74 @@ mod tests {
         assert!(cfg.git_root.is_none());
     }
 
+    // Project files are created owner-only, and doctor can untrack a committed .cred/.
+    #[test]
+    fn test_project_files_private_and_untrackable() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        Command::new("git")
+            .args(["init"])
+            .current_dir(root)
+            .output()
+            .unwrap();
+        project::init_at(root).unwrap();
+
+        let cred_dir = root.join(".cred");
+        let proj = project::Project {
+            vault_path: cred_dir.join("vault.enc"),
+            config_path: cred_dir.join("project.toml"),
+        };
+        let paths = proj.sensitive_paths();
+        assert!(paths.contains(&cred_dir));
+        assert!(paths.contains(&proj.vault_path));
+        assert!(paths.iter().all(|p| fsutil::is_private(p)));
+
+        assert!(proj.git_tracked_files().is_empty());
+        Command::new("git")
+            .args(["add", "-f", ".cred"])
+            .current_dir(root)
+            .output()
+            .unwrap();
+        let tracked = proj.git_tracked_files();
+        assert!(tracked.contains(&".cred/vault.enc".to_string()));
+        proj.untrack_files(&tracked).unwrap();
+        assert!(proj.git_tracked_files().is_empty());
+        assert!(proj.vault_path.exists());
+    }
+
+    // `secret set` values come from a file verbatim; prompting is refused when non-interactive.
+    #[test]
+    fn test_read_secret_value_sources() {
+        use crate::cli::{Cli, CliFlags};
+        use crate::io::{ValueSource, read_secret_value};
+        use clap::Parser;
+
+        let flags = CliFlags {
+            json: false,
+            non_interactive: true,
+            dry_run: false,
+            yes: false,
+        };
+        let dir = tempdir().unwrap();
+        let pem = dir.path().join("cert.pem");
+        let body = "-----BEGIN CERTIFICATE-----\nabc\n-----END CERTIFICATE-----\n";
+        fs::write(&pem, body).unwrap();
+
+        let from_file =
+            read_secret_value("CERT", ValueSource::File(pem.to_str().unwrap()), &flags).unwrap();
+        assert_eq!(from_file, body);
+        assert!(read_secret_value("K", ValueSource::Prompt, &flags).is_err());
+        assert!(read_secret_value("K", ValueSource::Arg(String::new()), &flags).is_err());
+
+        assert!(Cli::try_parse_from(["cred", "secret", "set", "K", "--stdin"]).is_ok());
+        assert!(Cli::try_parse_from(["cred", "secret", "set", "K"]).is_ok());
+        assert!(Cli::try_parse_from(["cred", "secret", "set", "K", "v", "--stdin"]).is_err());
+        assert!(
+            Cli::try_parse_from(["cred", "secret", "set", "K", "--stdin", "--from-file", "f"])
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_global_config_logic() {
         let dir = tempdir().unwrap();
@@ -220,7 +291,7 @@ mod tests {
 
         let raw = std::fs::read_to_string(&vault_path).unwrap();
         let parsed: EncFile = serde_json::from_str(&raw).unwrap();
-        assert_eq!(parsed.version, 2);
+        assert_eq!(parsed.version, 3);
         assert!(!parsed.nonce.is_empty());
         assert!(!parsed.ciphertext.is_empty());
     }
@@ -238,7 +309,7 @@ mod tests {
         let env_path = dir.path().join("sample.env");
         fs::write(&env_path, "EXISTING=new\nNEW=value\n").unwrap();
 
-        let entries = envfile::parse_env_file(&env_path).unwrap();
+        let entries = envfile::parse_env_file(&env_path, false).unwrap();
         let stats = envfile::import_entries(&entries, &mut v, false, false);
         v.save().unwrap();
 
@@ -264,7 +335,7 @@ mod tests {
         let env_path = dir.path().join("overwrite.env");
         fs::write(&env_path, "EXISTING=new\n").unwrap();
 
-        let entries = envfile::parse_env_file(&env_path).unwrap();
+        let entries = envfile::parse_env_file(&env_path, false).unwrap();
         let stats = envfile::import_entries(&entries, &mut v, true, false);
         v.save().unwrap();
 
@@ -276,6 +347,97 @@ mod tests {
         assert_eq!(reloaded.get("EXISTING"), Some(&"new".to_string()));
     }
 
+    // Importing into an environment overrides inherited keys instead of skipping them.
+    #[test]
+    fn test_env_import_into_layered_vault_overrides_inherited() {
+        let dir = tempdir().unwrap();
+        let key = get_test_key();
+
+        let mut base = vault::Vault::load(&dir.path().join("vault.enc"), key).unwrap();
+        base.set("SHARED", "base-value");
+        base.save().unwrap();
+
+        let staging_path = dir.path().join("staging.enc");
+        let parent = vault::Vault::load(&dir.path().join("vault.enc"), key).unwrap();
+        let mut staging = vault::Vault::load(&staging_path, key)
+            .unwrap()
+            .with_parent(parent);
+
+        let env_path = dir.path().join("staging.env");
+        fs::write(&env_path, "SHARED=staging-value\n").unwrap();
+
+        let entries = envfile::parse_env_file(&env_path, false).unwrap();
+        let stats = envfile::import_entries(&entries, &mut staging, false, false);
+        staging.save().unwrap();
+
+        assert_eq!(stats.added, 1);
+        assert_eq!(stats.skipped, 0);
+        assert!(!staging.is_inherited("SHARED"));
+
+        let staging_only = vault::Vault::load(&staging_path, key).unwrap();
+        assert_eq!(
+            staging_only.get("SHARED"),
+            Some(&"staging-value".to_string())
+        );
+    }
+
+    // Imported descriptions land on the entry; entries without one keep the existing description.
+    #[test]
+    fn test_import_entries_applies_descriptions() {
+        let dir = tempdir().unwrap();
+        let vault_path = dir.path().join("vault.enc");
+        let key = get_test_key();
+
+        let mut v = vault::Vault::load(&vault_path, key).unwrap();
+        v.set_with_metadata("KEEP", "old", SecretFormat::Raw, Some("kept".to_string()));
+
+        let entries = vec![
+            envfile::ImportedEntry {
+                key: "STRIPE_KEY".to_string(),
+                value: "sk_test_1".to_string(),
+                description: Some("Stripe Key".to_string()),
+                environment: None,
+                ..Default::default()
+            },
+            envfile::ImportedEntry::from(("KEEP".to_string(), "new".to_string())),
+        ];
+        let stats = envfile::import_entries(&entries, &mut v, true, false);
+
+        assert_eq!(stats.added, 1);
+        assert_eq!(stats.overwritten, 1);
+        assert_eq!(
+            v.get_entry("STRIPE_KEY").unwrap().description,
+            Some("Stripe Key".to_string())
+        );
+        let kept = v.get_entry("KEEP").unwrap();
+        assert_eq!(kept.value, "new");
+        assert_eq!(kept.description, Some("kept".to_string()));
+    }
+
+    // Source environment recorded on import survives a save/load cycle.
+    #[test]
+    fn test_import_environment_metadata_persists() {
+        let dir = tempdir().unwrap();
+        let vault_path = dir.path().join("vault.enc");
+        let key = get_test_key();
+
+        let mut v = vault::Vault::load(&vault_path, key).unwrap();
+        let entries = vec![envfile::ImportedEntry {
+            key: "API_KEY".to_string(),
+            value: "abc".to_string(),
+            description: Some("Primary API key".to_string()),
+            environment: Some("prd".to_string()),
+            ..Default::default()
+        }];
+        envfile::import_entries(&entries, &mut v, false, false);
+        v.save().unwrap();
+
+        let reloaded = vault::Vault::load(&vault_path, key).unwrap();
+        let entry = reloaded.get_entry("API_KEY").unwrap();
+        assert_eq!(entry.environment, Some("prd".to_string()));
+        assert_eq!(entry.description, Some("Primary API key".to_string()));
+    }
+
     #[test]
     fn test_env_export_guard_and_content() {
         let dir = tempdir().unwrap();
@@ -292,16 +454,693 @@ mod tests {
         fs::write(&out_path, "OLD=1\n").unwrap();
 
         let vault_view = vault::Vault::load(&vault_path, key).unwrap();
-        let err = envfile::export_env_file(&vault_view, &out_path, false, false).unwrap_err();
+        let err = formats::export_file(
+            &vault_view,
+            &out_path,
+            None,
+            FileFormat::Env,
+            false,
+            false,
+            false,
+        )
+        .unwrap_err();
         assert_eq!(err.code as i32, error::ExitCode::UserError as i32);
         assert_eq!(fs::read_to_string(&out_path).unwrap(), "OLD=1\n");
 
         // allow overwrite
-        let count = envfile::export_env_file(&vault_view, &out_path, true, false).unwrap();
+        let count = formats::export_file(
+            &vault_view,
+            &out_path,
+            None,
+            FileFormat::Env,
+            false,
+            true,
+            false,
+        )
+        .unwrap();
         assert_eq!(count, 2);
 
         let content = fs::read_to_string(&out_path).unwrap();
         assert_eq!(content, "A=1\nB=2\n");
+
+        // restrict to a subset (e.g. a scope), skipping unknown keys
+        let subset = vec!["B".to_string(), "GHOST".to_string()];
+        let count = formats::export_file(
+            &vault_view,
+            &out_path,
+            Some(&subset),
+            FileFormat::Env,
+            false,
+            true,
+            false,
+        )
+        .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(fs::read_to_string(&out_path).unwrap(), "B=2\n");
+    }
+
+    #[test]
+    fn test_env_parse_dotenv_dialect() {
+        let content = "# comment\n\
+                       export API_KEY=abc123\n\
+                       SPACED = padded value   # trailing comment\n\
+                       HASH=a#b\n\
+                       EMPTY=\n\
+                       SINGLE='literal \\n $HOME \"x\"'\n\
+                       DOUBLE=\"line1\\nline2 \\\"q\\\" \\\\ \\$HOME\" # note\n\
+                       TICK=`say 'hi' \"there\"`\n\
+                       PEM=\"-----BEGIN KEY-----\r\nMIIB\n-----END KEY-----\"\n";
+        let entries = envfile::parse_env_str(content, false).unwrap();
+        let get = |k: &str| entries.iter().find(|(key, _)| key == k).unwrap().1.as_str();
+
+        assert_eq!(entries.len(), 8);
+        assert_eq!(get("API_KEY"), "abc123");
+        assert_eq!(get("SPACED"), "padded value");
+        assert_eq!(get("HASH"), "a#b");
+        assert_eq!(get("EMPTY"), "");
+        assert_eq!(get("SINGLE"), "literal \\n $HOME \"x\"");
+        assert_eq!(get("DOUBLE"), "line1\nline2 \"q\" \\ $HOME");
+        assert_eq!(get("TICK"), "say 'hi' \"there\"");
+        assert_eq!(get("PEM"), "-----BEGIN KEY-----\nMIIB\n-----END KEY-----");
+
+        for (bad, msg) in [
+            ("A=1\nNOEQUALS\n", "line 2: expected KEY=VALUE"),
+            ("=value\n", "key cannot be empty"),
+            ("A=\"open\nstill open\n", "line 1: unterminated \" quote"),
+            ("A='x' trailing\n", "unexpected 'trailing'"),
+        ] {
+            let err = envfile::parse_env_str(bad, false).unwrap_err();
+            assert!(
+                err.error.to_string().contains(msg),
+                "{}: {}",
+                bad,
+                err.error
+            );
+        }
+    }
+
+    #[test]
+    fn test_env_parse_expansion_is_opt_in() {
+        let content = "HOST=db.local\n\
+                       URL=postgres://${HOST}/app\n\
+                       QUOTED=\"${HOST}:${PORT:-5432} \\${HOST}\"\n\
+                       LITERAL='${HOST}'\n";
+        let expanded = envfile::parse_env_str(content, true).unwrap();
+        assert_eq!(expanded[1].1, "postgres://db.local/app");
+        assert_eq!(expanded[2].1, "db.local:5432 ${HOST}");
+        assert_eq!(expanded[3].1, "${HOST}");
+
+        let verbatim = envfile::parse_env_str(content, false).unwrap();
+        assert_eq!(verbatim[1].1, "postgres://${HOST}/app");
+        assert_eq!(verbatim[2].1, "${HOST}:${PORT:-5432} ${HOST}");
+    }
+
+    // Whatever the vault holds, export → import gives back the same value and format.
+    #[test]
+    fn test_env_export_round_trips_every_format() {
+        let dir = tempdir().unwrap();
+        let key = get_test_key();
+        let mut v = vault::Vault::load(&dir.path().join("vault.enc"), key).unwrap();
+        let values = [
+            ("RAW", "plain-token_1.2", SecretFormat::Raw),
+            ("TRICKY", " it's \"$HOME\" #x \\n `id` ", SecretFormat::Raw),
+            ("MULTI", "line one\n\tline two\r\n", SecretFormat::Multiline),
+            (
+                "PEM",
+                "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n",
+                SecretFormat::Pem,
+            ),
+            (
+                "B64",
+                "aGVsbG8gd29ybGQgaGVsbG8gd29ybGQ=",
+                SecretFormat::Base64,
+            ),
+            ("JSON", "{\"a\": [1, \"${X}\"]}", SecretFormat::Json),
+        ];
+        for (k, value, _) in &values {
+            v.set(k, value);
+        }
+
+        let out = dir.path().join("round.env");
+        formats::export_file(&v, &out, None, FileFormat::Env, false, false, false).unwrap();
+        let content = fs::read_to_string(&out).unwrap();
+        assert!(content.contains("RAW=plain-token_1.2\n"), "{}", content);
+
+        for expand in [false, true] {
+            let entries = envfile::parse_env_file(&out, expand).unwrap();
+            assert_eq!(entries.len(), values.len());
+            for (k, value, format) in &values {
+                let (_, parsed) = entries.iter().find(|(key, _)| key == k).unwrap();
+                assert_eq!(parsed, value, "{}", k);
+                assert_eq!(vault::Vault::detect_format(parsed), *format, "{}", k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_structured_import_flattens_nested_keys() {
+        let json = r#"{"API_KEY": "abc", "db": {"url": "postgres://h/db", "pool": {"size": 5}},
+                       "ports": [80, 443], "debug": true, "unset": null}"#;
+        let yaml = "API_KEY: abc\ndb:\n  url: postgres://h/db\n  pool:\n    size: 5\n\
+                    ports: [80, 443]\ndebug: true\nunset: ~\n";
+        let toml = "API_KEY = \"abc\"\nports = [80, 443]\ndebug = true\n\
+                    [db]\nurl = \"postgres://h/db\"\n[db.pool]\nsize = 5\n";
+        for (content, format) in [
+            (json, FileFormat::Json),
+            (yaml, FileFormat::Yaml),
+            (toml, FileFormat::Toml),
+        ] {
+            let mut entries: Vec<_> = formats::parse_str(content, format, "__")
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.key, e.value))
+                .collect();
+            entries.sort();
+            assert_eq!(
+                entries,
+                vec![
+                    ("API_KEY".to_string(), "abc".to_string()),
+                    ("db__pool__size".to_string(), "5".to_string()),
+                    ("db__url".to_string(), "postgres://h/db".to_string()),
+                    ("debug".to_string(), "true".to_string()),
+                    ("ports".to_string(), "[80,443]".to_string()),
+                ],
+                "{}",
+                format
+            );
+        }
+
+        let clash = r#"{"a_b": "1", "a": {"b": "2"}}"#;
+        let err = formats::parse_str(clash, FileFormat::Json, "_").unwrap_err();
+        assert!(err.error.to_string().contains("more than once"));
+        assert_eq!(
+            formats::parse_str(clash, FileFormat::Json, ".")
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(formats::parse_str("[1, 2]", FileFormat::Json, "_").is_err());
+
+        assert_eq!(
+            FileFormat::detect(std::path::Path::new("secrets.YML")),
+            FileFormat::Yaml
+        );
+        assert_eq!(
+            FileFormat::detect(std::path::Path::new(".env.production")),
+            FileFormat::Env
+        );
+    }
+
+    // Flat exports keep values exactly; metadata exports also keep format, description
+    // and timestamps.
+    #[test]
+    fn test_structured_export_round_trips() {
+        let dir = tempdir().unwrap();
+        let key = get_test_key();
+        let mut v = vault::Vault::load(&dir.path().join("vault.enc"), key).unwrap();
+        v.set("TOKEN", "a \"quoted\" $value");
+        v.set(
+            "TLS_CERT",
+            "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n",
+        );
+        v.set_with_metadata(
+            "NOTE",
+            "{not json",
+            SecretFormat::Multiline,
+            Some("hand-set format".to_string()),
+        );
+        v.set_environment("NOTE", Some("prd".to_string()));
+
+        for format in [FileFormat::Json, FileFormat::Yaml, FileFormat::Toml] {
+            let out = dir.path().join(format!("flat.{}", format));
+            formats::export_file(&v, &out, None, format, false, false, false).unwrap();
+            let entries = formats::parse_file(&out, None, "_", false).unwrap();
+            assert_eq!(entries.len(), 3, "{}", format);
+            for entry in entries {
+                assert_eq!(Some(&entry.value), v.get(&entry.key), "{}", format);
+                assert_eq!(entry.format, None);
+            }
+        }
+
+        let toml_out = dir.path().join("meta.toml");
+        let err = formats::export_file(&v, &toml_out, None, FileFormat::Toml, true, false, false)
+            .unwrap_err();
+        assert!(err.error.to_string().contains("JSON and YAML"));
+
+        for format in [FileFormat::Json, FileFormat::Yaml] {
+            let out = dir.path().join(format!("meta.{}", format));
+            formats::export_file(&v, &out, None, format, true, false, false).unwrap();
+            let entries = formats::parse_file(&out, None, "_", false).unwrap();
+
+            let mut target =
+                vault::Vault::load(&dir.path().join(format!("{}.enc", format)), key).unwrap();
+            envfile::import_entries(&entries, &mut target, false, false);
+            for name in ["TOKEN", "TLS_CERT", "NOTE"] {
+                let (a, b) = (v.get_entry(name).unwrap(), target.get_entry(name).unwrap());
+                assert_eq!(a.value, b.value);
+                assert_eq!(a.format, b.format, "{} {}", format, name);
+                assert_eq!(a.description, b.description);
+                assert_eq!(a.environment, b.environment);
+                assert_eq!(a.created_at, b.created_at);
+                assert_eq!(a.updated_at, b.updated_at);
+            }
+        }
+    }
+
+    // Environment vaults read through to their parent while writes stay local.
+    #[test]
+    fn test_layered_vault_inherits_and_overrides() {
+        let dir = tempdir().unwrap();
+        let key = get_test_key();
+
+        let mut base = vault::Vault::load(&dir.path().join("vault.enc"), key).unwrap();
+        base.set("SHARED", "base-value");
+        base.set("DB_URL", "postgres://dev");
+        base.save().unwrap();
+
+        let prod_path = dir.path().join("prod.enc");
+        let parent = vault::Vault::load(&dir.path().join("vault.enc"), key).unwrap();
+        let mut prod = vault::Vault::load(&prod_path, key)
+            .unwrap()
+            .with_parent(parent);
+        prod.set("DB_URL", "postgres://prod");
+        prod.save().unwrap();
+
+        assert_eq!(prod.get("SHARED"), Some(&"base-value".to_string()));
+        assert_eq!(prod.get("DB_URL"), Some(&"postgres://prod".to_string()));
+        assert!(prod.is_inherited("SHARED"));
+        assert!(!prod.is_inherited("DB_URL"));
+        assert_eq!(prod.list().len(), 2);
+        assert_eq!(prod.effective_entries().len(), 2);
+
+        // Only the override was written to the environment's own file.
+        let prod_only = vault::Vault::load(&prod_path, key).unwrap();
+        assert_eq!(prod_only.list().len(), 1);
+        let base_reloaded = vault::Vault::load(&dir.path().join("vault.enc"), key).unwrap();
+        assert_eq!(
+            base_reloaded.get("DB_URL"),
+            Some(&"postgres://dev".to_string())
+        );
+    }
+
+    // Overwrites keep a bounded history that survives save/load and can be rolled back.
+    #[test]
+    fn test_secret_history_bounded_and_rollback() {
+        let dir = tempdir().unwrap();
+        let key = get_test_key();
+        let path = dir.path().join("vault.enc");
+
+        let mut v = vault::Vault::load(&path, key)
+            .unwrap()
+            .with_history_limit(2);
+        v.set("TOKEN", "one");
+        v.set("TOKEN", "two");
+        v.set("TOKEN", "two"); // unchanged value does not create a version
+        v.set("TOKEN", "three");
+        v.set("TOKEN", "four");
+        v.save().unwrap();
+
+        let mut v = vault::Vault::load(&path, key)
+            .unwrap()
+            .with_history_limit(2);
+        let entry = v.get_entry("TOKEN").unwrap();
+        assert_eq!(entry.version, 4);
+        let versions: Vec<u32> = entry.history.iter().map(|h| h.version).collect();
+        assert_eq!(versions, vec![2, 3]);
+        assert!(entry.history_version(1).is_none());
+
+        let new_version = v.rollback("TOKEN", 2).unwrap();
+        assert_eq!(new_version, 5);
+        assert_eq!(v.get("TOKEN"), Some(&"two".to_string()));
+        // the rolled-back-from value is itself kept
+        let entry = v.get_entry("TOKEN").unwrap();
+        assert_eq!(entry.history_version(4).unwrap().value, "four");
+        assert!(v.rollback("TOKEN", 1).is_err());
+        assert!(v.rollback("MISSING", 1).is_err());
+    }
+
+    // Rotation policies persist, flag overdue entries and regenerate with history kept.
+    #[test]
+    fn test_rotation_policy_regenerates_overdue_secrets() {
+        let dir = tempdir().unwrap();
+        let key = get_test_key();
+        let path = dir.path().join("vault.enc");
+
+        let mut v = vault::Vault::load(&path, key).unwrap();
+        v.set_with_metadata(
+            "API_TOKEN",
+            "old-token",
+            SecretFormat::Raw,
+            Some("billing API".to_string()),
+        );
+        v.set("DEPLOY_KEY", "old-key");
+        v.set("PLAIN", "never rotated");
+        let policy = |recipe| rotation::RotationPolicy {
+            max_age_days: 30,
+            recipe,
+        };
+        assert!(v.set_rotation(
+            "API_TOKEN",
+            Some(policy(generate::Recipe::Hex { bytes: 16 }))
+        ));
+        assert!(v.set_rotation("DEPLOY_KEY", Some(policy(generate::Recipe::Ed25519))));
+        assert!(!v.set_rotation("MISSING", None));
+        v.set_timestamps(
+            "API_TOKEN",
+            None,
+            Some(chrono::Utc::now() - chrono::Duration::days(31)),
+        );
+        v.save().unwrap();
+
+        let mut v = vault::Vault::load(&path, key).unwrap();
+        let now = chrono::Utc::now();
+        assert_eq!(rotation::due_keys(&v, now), vec!["API_TOKEN"]);
+        assert!(!v.get_entry("PLAIN").unwrap().rotation_due(now));
+
+        let rotated = rotation::rotate(&mut v, "API_TOKEN").unwrap();
+        assert_eq!(rotated.version, 2);
+        assert!(rotated.public_key.is_none());
+        let entry = v.get_entry("API_TOKEN").unwrap();
+        assert_eq!(entry.value.len(), 32);
+        assert_eq!(entry.history_version(1).unwrap().value, "old-token");
+        assert_eq!(entry.description.as_deref(), Some("billing API"));
+        assert!(entry.rotation.is_some());
+        assert!(rotation::due_keys(&v, now).is_empty());
+
+        let rotated = rotation::rotate(&mut v, "DEPLOY_KEY").unwrap();
+        assert_eq!(rotated.public_key.as_deref(), Some("DEPLOY_KEY_PUBLIC"));
+        assert!(v.get("DEPLOY_KEY_PUBLIC").is_some());
+        assert!(rotation::rotate(&mut v, "PLAIN").is_err());
+        assert!(rotation::rotate(&mut v, "MISSING").is_err());
+    }
+
+    // Expiry belongs to a value: it persists, clears on overwrite and returns on rollback.
+    #[test]
+    fn test_expiry_follows_the_value() {
+        let dir = tempdir().unwrap();
+        let key = get_test_key();
+        let path = dir.path().join("vault.enc");
+        let now = chrono::Utc::now();
+
+        let mut v = vault::Vault::load(&path, key).unwrap();
+        v.set("GITHUB_PAT", "ghp_old");
+        v.set("STRIPE_KEY", "sk_live");
+        v.set("NO_EXPIRY", "forever");
+        assert!(v.set_expiry("GITHUB_PAT", Some(now - chrono::Duration::days(1))));
+        assert!(v.set_expiry("STRIPE_KEY", Some(now + chrono::Duration::days(3))));
+        v.save().unwrap();
+
+        let mut v = vault::Vault::load(&path, key).unwrap();
+        let (expired, expiring) = expiry::summarize(v.effective_entries(), now);
+        assert_eq!(expired, vec!["GITHUB_PAT"]);
+        assert_eq!(expiring, vec!["STRIPE_KEY"]);
+
+        // Metadata exports carry the date.
+        let entries = v.select_entries(None).unwrap();
+        let doc = formats::render(&entries, FileFormat::Json, true).unwrap();
+        let imported = formats::parse_str(&doc, FileFormat::Json, "_").unwrap();
+        let pat = imported.iter().find(|e| e.key == "GITHUB_PAT").unwrap();
+        assert!(pat.expires_at.is_some());
+
+        v.set("GITHUB_PAT", "ghp_new");
+        assert!(v.get_entry("GITHUB_PAT").unwrap().expires_at.is_none());
+        v.set("STRIPE_KEY", "sk_live");
+        assert!(v.get_entry("STRIPE_KEY").unwrap().expires_at.is_some());
+
+        v.rollback("GITHUB_PAT", 1).unwrap();
+        assert_eq!(
+            v.get_entry("GITHUB_PAT").unwrap().expiry_status(now),
+            Some(expiry::Status::Expired)
+        );
+    }
+
+    // Passphrase-wrapped key files round-trip through disk and reject wrong passphrases.
+    #[test]
+    fn test_wrapped_key_file_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(keyfile::KEY_FILE_NAME);
+        let key = get_test_key();
+        // Cheap parameters keep the test fast; the stored params are used to unwrap.
+        let params = keyfile::KdfParams::fast();
+
+        keyfile::WrappedKey::seal(&key, "correct horse", params)
+            .unwrap()
+            .write(&path)
+            .unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("argon2id"));
+        assert!(content.contains("\"memory_kib\": 1024"));
+
+        let wrapped = keyfile::WrappedKey::read(&path).unwrap();
+        assert_eq!(wrapped.open("correct horse").unwrap(), key);
+        assert!(wrapped.open("wrong horse").is_err());
+    }
+
+    // Recovery keys survive both armor encodings and carry their project id.
+    #[test]
+    fn test_recovery_blob_round_trip() {
+        let key = get_test_key();
+        let project_id = uuid::Uuid::new_v4();
+        let params = keyfile::KdfParams::fast();
+        let blob = recovery::RecoveryBlob::seal(project_id, &key, "backup pw", params).unwrap();
+        for paper in [false, true] {
+            let text = blob.to_armored(paper).unwrap();
+            assert!(text.contains(&project_id.to_string()));
+            let parsed = recovery::RecoveryBlob::from_armored(&text).unwrap();
+            assert_eq!(parsed.project_id, project_id);
+            assert_eq!(parsed.open("backup pw").unwrap(), key);
+            assert!(parsed.open("nope").is_err());
+        }
+    }
+
+    // Pasted blobs and bundles cannot demand unbounded Argon2id costs.
+    #[test]
+    fn test_wrapped_key_rejects_excessive_kdf_params() {
+        let key = get_test_key();
+        let wrapped = keyfile::WrappedKey::seal(&key, "pw", keyfile::KdfParams::fast()).unwrap();
+        let bytes = wrapped.to_bytes().unwrap();
+        assert_eq!(
+            keyfile::WrappedKey::from_bytes(&bytes)
+                .unwrap()
+                .open("pw")
+                .unwrap(),
+            key
+        );
+        for (offset, value) in [(0, 2 * 1024 * 1024), (4, 1000), (8, 255)] {
+            let mut tampered = bytes.clone();
+            tampered[offset..offset + 4].copy_from_slice(&u32::to_be_bytes(value));
+            assert!(keyfile::WrappedKey::from_bytes(&tampered).is_err());
+        }
+
+        // The same bounds apply to JSON-encoded keys such as those embedded in bundles.
+        let mut json = serde_json::to_value(&wrapped).unwrap();
+        json["kdf"]["memory_kib"] = serde_json::json!(u32::MAX);
+        let inflated: keyfile::WrappedKey = serde_json::from_value(json).unwrap();
+        assert!(inflated.open("pw").is_err());
+    }
+
+    // Rotation re-encrypts every vault file, re-wraps the key file and leaves no recovery copies.
+    #[test]
+    fn test_rotate_master_key_reencrypts_vaults() {
+        let dir = tempdir().unwrap();
+        let cred_dir = dir.path().join(".cred");
+        fs::create_dir_all(cred_dir.join("envs")).unwrap();
+        fs::write(
+            cred_dir.join("project.toml"),
+            "id = \"7f1e8c5a-0000-4000-8000-000000000001\"\n[environments.prod]\n",
+        )
+        .unwrap();
+        let proj = project::Project {
+            vault_path: cred_dir.join("vault.enc"),
+            config_path: cred_dir.join("project.toml"),
+        };
+        let old_key = get_test_key();
+        let params = keyfile::KdfParams::fast();
+        keyfile::WrappedKey::seal(&old_key, "pw", params)
+            .unwrap()
+            .write(&proj.key_file_path())
+            .unwrap();
+
+        let mut base = vault::Vault::load(&proj.vault_path, old_key).unwrap();
+        base.set("A", "1");
+        base.set("A", "2");
+        base.save().unwrap();
+        let prod_path = proj.env_vault_path("prod");
+        let mut prod = vault::Vault::load(&prod_path, old_key).unwrap();
+        prod.set("B", "3");
+        prod.save().unwrap();
+
+        let rotation = proj.rotate_master_key_with(Some("pw")).unwrap();
+        assert_eq!(rotation.vault_files.len(), 2);
+        assert_eq!(rotation.key_storage, "passphrase");
+
+        let new_key = keyfile::WrappedKey::read(&proj.key_file_path())
+            .unwrap()
+            .open("pw")
+            .unwrap();
+        assert_ne!(new_key, old_key);
+        assert!(vault::Vault::load(&proj.vault_path, old_key).is_err());
+        let base = vault::Vault::load(&proj.vault_path, new_key).unwrap();
+        assert_eq!(base.get("A"), Some(&"2".to_string()));
+        assert_eq!(base.get_entry("A").unwrap().history.len(), 1);
+        let prod = vault::Vault::load(&prod_path, new_key).unwrap();
+        assert_eq!(prod.get("B"), Some(&"3".to_string()));
+
+        let leftovers: Vec<_> = fs::read_dir(&cred_dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".pre-rotate"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    // A recovered key goes back into the key file under a new passphrase, not the keyring.
+    #[test]
+    fn test_restore_master_key_rewraps_key_file() {
+        let dir = tempdir().unwrap();
+        let cred_dir = dir.path().join(".cred");
+        fs::create_dir_all(&cred_dir).unwrap();
+        fs::write(
+            cred_dir.join("project.toml"),
+            "id = \"7f1e8c5a-0000-4000-8000-000000000003\"\n",
+        )
+        .unwrap();
+        let proj = project::Project {
+            vault_path: cred_dir.join("vault.enc"),
+            config_path: cred_dir.join("project.toml"),
+        };
+        let key = get_test_key();
+        keyfile::WrappedKey::seal(&key, "forgotten", keyfile::KdfParams::fast())
+            .unwrap()
+            .write(&proj.key_file_path())
+            .unwrap();
+
+        assert!(proj.restore_master_key(&key, None).is_err());
+        proj.restore_master_key(&key, Some("new pw")).unwrap();
+        assert_eq!(proj.key_storage(), "passphrase");
+        let wrapped = keyfile::WrappedKey::read(&proj.key_file_path()).unwrap();
+        assert_eq!(wrapped.open("new pw").unwrap(), key);
+        assert!(wrapped.open("forgotten").is_err());
+    }
+
+    // Rotation keeps history beyond the default limit when project.toml raises it.
+    #[test]
+    fn test_rotate_master_key_keeps_long_history() {
+        let dir = tempdir().unwrap();
+        let cred_dir = dir.path().join(".cred");
+        fs::create_dir_all(&cred_dir).unwrap();
+        fs::write(
+            cred_dir.join("project.toml"),
+            "id = \"7f1e8c5a-0000-4000-8000-000000000002\"\nhistory_limit = 30\n",
+        )
+        .unwrap();
+        let proj = project::Project {
+            vault_path: cred_dir.join("vault.enc"),
+            config_path: cred_dir.join("project.toml"),
+        };
+        let old_key = get_test_key();
+        keyfile::WrappedKey::seal(&old_key, "pw", keyfile::KdfParams::fast())
+            .unwrap()
+            .write(&proj.key_file_path())
+            .unwrap();
+
+        let mut base = vault::Vault::load(&proj.vault_path, old_key)
+            .unwrap()
+            .with_history_limit(30);
+        for i in 0..=20 {
+            base.set("TOKEN", &i.to_string());
+        }
+        base.save().unwrap();
+
+        proj.rotate_master_key_with(Some("pw")).unwrap();
+        let new_key = keyfile::WrappedKey::read(&proj.key_file_path())
+            .unwrap()
+            .open("pw")
+            .unwrap();
+        let base = vault::Vault::load(&proj.vault_path, new_key).unwrap();
+        let entry = base.get_entry("TOKEN").unwrap();
+        assert_eq!(entry.value, "20");
+        assert_eq!(entry.history.len(), 20);
+        assert_eq!(entry.history[0].value, "0");
+    }
+
+    // Rotation locks every vault before re-encrypting any of them and holds the locks until
+    // the new key is stored. A second handle on the lock file stands in for another process.
+    #[test]
+    fn test_rotate_master_key_holds_vault_locks() {
+        use std::fs::{File, TryLockError};
+        use std::time::{Duration, Instant};
+
+        let dir = tempdir().unwrap();
+        let cred_dir = dir.path().join(".cred");
+        fs::create_dir_all(cred_dir.join("envs")).unwrap();
+        fs::write(
+            cred_dir.join("project.toml"),
+            "id = \"7f1e8c5a-0000-4000-8000-000000000003\"\n[environments.prod]\n",
+        )
+        .unwrap();
+        let proj = project::Project {
+            vault_path: cred_dir.join("vault.enc"),
+            config_path: cred_dir.join("project.toml"),
+        };
+        let old_key = get_test_key();
+        keyfile::WrappedKey::seal(&old_key, "pw", keyfile::KdfParams::fast())
+            .unwrap()
+            .write(&proj.key_file_path())
+            .unwrap();
+        let prod_path = proj.env_vault_path("prod");
+        // Loaded vaults hold their lock, so drop them before probing from outside.
+        {
+            let mut base = vault::Vault::load(&proj.vault_path, old_key).unwrap();
+            base.set("A", "1");
+            base.save().unwrap();
+            let mut prod = vault::Vault::load(&prod_path, old_key).unwrap();
+            prod.set("B", "2");
+            prod.save().unwrap();
+        }
+        let before = fs::read(&proj.vault_path).unwrap();
+
+        let open_lock = |path: &std::path::Path| {
+            File::options()
+                .write(true)
+                .open(fsutil::lock_path(path))
+                .unwrap()
+        };
+        // Hold the prod vault so rotation stalls after taking its other locks.
+        let prod_lock = open_lock(&prod_path);
+        prod_lock.lock().unwrap();
+
+        let rotation = std::thread::spawn({
+            let proj = project::Project {
+                vault_path: proj.vault_path.clone(),
+                config_path: proj.config_path.clone(),
+            };
+            move || proj.rotate_master_key_with(Some("pw")).map(|_| ())
+        });
+
+        let base_lock = open_lock(&proj.vault_path);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            match base_lock.try_lock() {
+                Err(TryLockError::WouldBlock) => break,
+                Ok(()) => base_lock.unlock().unwrap(),
+                Err(e) => panic!("{e}"),
+            }
+            assert!(Instant::now() < deadline, "rotation never locked vault.enc");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        // Mid-rotation: project.toml and the base vault are locked, nothing is re-encrypted yet.
+        assert!(matches!(
+            open_lock(&proj.config_path).try_lock(),
+            Err(TryLockError::WouldBlock)
+        ));
+        assert_eq!(fs::read(&proj.vault_path).unwrap(), before);
+
+        prod_lock.unlock().unwrap();
+        rotation.join().unwrap().unwrap();
+        assert!(base_lock.try_lock().is_ok());
+        base_lock.unlock().unwrap();
+        assert!(vault::Vault::load(&proj.vault_path, old_key).is_err());
     }
 
     // Dry-run ordering: diff/plans are deterministic and sorted.
@@ -667,7 +1506,7 @@ mod tests {
 
         let v1_file = serde_json::json!({
             "version": 1,
-            "nonce": BASE64.encode(&nonce),
+            "nonce": BASE64.encode(nonce),
             "ciphertext": BASE64.encode(&ciphertext)
         });
         fs::write(&vault_path, serde_json::to_string_pretty(&v1_file).unwrap()).unwrap();
@@ -689,9 +1528,9 @@ mod tests {
         assert_eq!(ml_entry.format, SecretFormat::Multiline);
     }
 
-    // After migration and save, file is v2 format.
+    // After migration and save, file is in the current (v3) format.
     #[test]
-    fn test_migration_saves_as_v2() {
+    fn test_migration_saves_as_v3() {
         use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
         use chacha20poly1305::{
             ChaCha20Poly1305,
@@ -710,7 +1549,7 @@ mod tests {
         let ciphertext = cipher.encrypt(&nonce, plaintext.as_ref()).unwrap();
         let v1_file = serde_json::json!({
             "version": 1,
-            "nonce": BASE64.encode(&nonce),
+            "nonce": BASE64.encode(nonce),
             "ciphertext": BASE64.encode(&ciphertext)
         });
         fs::write(&vault_path, serde_json::to_string(&v1_file).unwrap()).unwrap();
@@ -719,20 +1558,143 @@ mod tests {
         let v = vault::Vault::load(&vault_path, key).unwrap();
         v.save().unwrap();
 
-        // Check file is now v2
+        // Check file is now v3
         #[derive(serde::Deserialize)]
         struct EncFile {
             version: u8,
         }
         let raw = fs::read_to_string(&vault_path).unwrap();
         let parsed: EncFile = serde_json::from_str(&raw).unwrap();
-        assert_eq!(parsed.version, 2);
+        assert_eq!(parsed.version, 3);
 
         // Reload works
         let v2 = vault::Vault::load(&vault_path, key).unwrap();
         assert_eq!(v2.get("KEY"), Some(&"value".to_string()));
     }
 
+    // Each save keeps the previous file as vault.enc.bak; a corrupt vault.enc falls back to it.
+    #[test]
+    fn test_vault_backup_fallback() {
+        let dir = tempdir().unwrap();
+        let vault_path = dir.path().join("vault.enc");
+        let backup = vault::backup_path(&vault_path);
+        let key = get_test_key();
+
+        let mut v = vault::Vault::load(&vault_path, key).unwrap();
+        v.set("KEY", "first");
+        v.save().unwrap();
+        assert!(!backup.exists());
+        v.set("KEY", "second");
+        v.save().unwrap();
+        drop(v);
+        assert!(backup.exists());
+
+        // Torn write: main file truncated
+        let raw = fs::read_to_string(&vault_path).unwrap();
+        fs::write(&vault_path, &raw[..raw.len() / 2]).unwrap();
+
+        let mut v = vault::Vault::load(&vault_path, key).unwrap();
+        assert_eq!(v.get("KEY"), Some(&"first".to_string()));
+
+        // Saving the recovered vault repairs vault.enc without clobbering the backup
+        let good_backup = fs::read(&backup).unwrap();
+        v.set("KEY", "third");
+        v.save().unwrap();
+        drop(v);
+        assert_eq!(fs::read(&backup).unwrap(), good_backup);
+        let v = vault::Vault::load(&vault_path, key).unwrap();
+        assert_eq!(v.get("KEY"), Some(&"third".to_string()));
+    }
+
+    // The backup doesn't mask a wrong key.
+    #[test]
+    fn test_vault_backup_wrong_key_still_fails() {
+        let dir = tempdir().unwrap();
+        let vault_path = dir.path().join("vault.enc");
+        let key = get_test_key();
+
+        let mut v = vault::Vault::load(&vault_path, key).unwrap();
+        v.set("KEY", "a");
+        v.save().unwrap();
+        v.set("KEY", "b");
+        v.save().unwrap();
+        drop(v);
+
+        let err = vault::Vault::load(&vault_path, [9u8; 32]).unwrap_err();
+        assert!(err.to_string().contains("Decryption failed"));
+    }
+
+    // v2 vaults migrate to v3, whose header is authenticated and bound to the project.
+    #[test]
+    fn test_v2_to_v3_migration_binds_project() {
+        use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+        use chacha20poly1305::{
+            ChaCha20Poly1305,
+            aead::{Aead, AeadCore, KeyInit, OsRng},
+        };
+
+        let dir = tempdir().unwrap();
+        let vault_path = dir.path().join("vault.enc");
+        let key = get_test_key();
+        let project = uuid::Uuid::new_v4();
+
+        // Create a v2 vault (no associated data)
+        let payload = serde_json::json!({
+            "version": 2,
+            "secrets": {
+                "KEY": {
+                    "value": "value",
+                    "format": "raw",
+                    "created_at": "2025-01-01T00:00:00Z",
+                    "updated_at": "2025-01-01T00:00:00Z"
+                }
+            }
+        });
+        let plaintext = serde_json::to_vec(&payload).unwrap();
+        let cipher = ChaCha20Poly1305::new(&key.into());
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher.encrypt(&nonce, plaintext.as_ref()).unwrap();
+        let v2_file = serde_json::json!({
+            "version": 2,
+            "nonce": BASE64.encode(nonce),
+            "ciphertext": BASE64.encode(&ciphertext)
+        });
+        fs::write(&vault_path, serde_json::to_string(&v2_file).unwrap()).unwrap();
+
+        let v = vault::Vault::load_for_project(&vault_path, key, Some(project)).unwrap();
+        assert_eq!(v.get("KEY"), Some(&"value".to_string()));
+        v.save().unwrap();
+
+        let raw: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&vault_path).unwrap()).unwrap();
+        assert_eq!(raw["version"], 3);
+        assert_eq!(raw["project_id"], project.to_string());
+        assert_eq!(raw["cipher"], "chacha20poly1305");
+
+        // Bound to its project
+        assert!(vault::Vault::load_for_project(&vault_path, key, Some(project)).is_ok());
+        assert!(vault::Vault::load(&vault_path, key).is_ok());
+        let err = vault::Vault::load_for_project(&vault_path, key, Some(uuid::Uuid::new_v4()))
+            .unwrap_err();
+        assert!(err.to_string().contains("belongs to project"));
+
+        // Tampering with any header field breaks authentication
+        fs::remove_file(vault::backup_path(&vault_path)).unwrap();
+        for (field, value) in [
+            (
+                "project_id",
+                serde_json::json!(uuid::Uuid::new_v4().to_string()),
+            ),
+            ("version", serde_json::json!(2)),
+            ("version", serde_json::json!(1)),
+        ] {
+            let mut tampered = raw.clone();
+            tampered[field] = value;
+            fs::write(&vault_path, tampered.to_string()).unwrap();
+            assert!(vault::Vault::load(&vault_path, key).is_err(), "{}", field);
+        }
+    }
+
     // set_hash updates the hash field.
     #[test]
     fn test_set_hash() {
@@ -812,7 +1774,7 @@ mod tests {
 
         let fake_vault = serde_json::json!({
             "version": 99,
-            "nonce": BASE64.encode(&nonce),
+            "nonce": BASE64.encode(nonce),
             "ciphertext": BASE64.encode(&ciphertext)
         });
         fs::write(&vault_path, serde_json::to_string(&fake_vault).unwrap()).unwrap();
@@ -2,15 +2,27 @@
 //!
 //! # Vault Schema Versions
 //! - **v1**: Legacy format where decrypted payload is `HashMap<String, String>`
-//! - **v2**: Current format with `SecretEntry` containing value, format, hash, timestamps, description
+//! - **v2**: `SecretEntry` containing value, format, hash, timestamps, description
+//! - **v3**: Current format; same payload as v2, but the envelope header (version, project
+//!   UUID, KDF and cipher identifiers) is authenticated as AEAD associated data, so a vault
+//!   can't be swapped between projects or have its version downgraded
 //!
-//! Migration from v1 to v2 is automatic on load; v2 is always written on save.
+//! Older versions are migrated automatically on load; v3 is always written on save.
+//!
+//! Saves are atomic (temp file + fsync + rename) and keep the previous file as
+//! `vault.enc.bak`; if `vault.enc` can't be read, `load` falls back to the backup with a
+//! warning. A loaded vault holds an advisory lock on its file until dropped, so concurrent
+//! cred processes serialize their load-modify-save cycles.
+//!
+//! Each v2 entry also carries a bounded `history` of prior values (oldest first) so a
+//! bad overwrite can be rolled back; entries written before history existed load with
+//! an empty history.
 
 use anyhow::{Context, Result, bail};
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use chacha20poly1305::{
     ChaCha20Poly1305, Nonce,
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -18,21 +30,59 @@ use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use uuid::Uuid;
 use zeroize::Zeroize;
 
+use crate::cert;
+use crate::expiry;
+use crate::fsutil;
+use crate::rotation::RotationPolicy;
+
 /// Current vault schema version.
-const CURRENT_VERSION: u8 = 2;
+const CURRENT_VERSION: u8 = 3;
+
+/// Cipher identifier recorded in (and authenticated by) the v3 envelope.
+const VAULT_CIPHER: &str = "chacha20poly1305";
+
+/// KDF identifier for the vault key; "none" means the 32-byte master key is used directly.
+const VAULT_KDF: &str = "none";
+
+/// Prior values kept per secret unless the project configures `history_limit`.
+pub const DEFAULT_HISTORY_LIMIT: usize = 10;
 
 /// On-disk representation of the vault file (envelope).
 #[derive(Serialize, Deserialize)]
 struct EncryptedVaultFile {
     version: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cipher: Option<String>,
     nonce: String,
     ciphertext: String,
 }
 
+impl EncryptedVaultFile {
+    /// Associated data binding the header to the ciphertext (empty before v3).
+    fn aad(&self) -> Vec<u8> {
+        if self.version < 3 {
+            return Vec::new();
+        }
+        format!(
+            "cred-vault:v{}:{}:{}:{}",
+            self.version,
+            self.project_id.map(|id| id.to_string()).unwrap_or_default(),
+            self.kdf.as_deref().unwrap_or_default(),
+            self.cipher.as_deref().unwrap_or_default()
+        )
+        .into_bytes()
+    }
+}
+
 /// Format hint for secret values.
-#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum SecretFormat {
     /// Single-line text (default)
@@ -90,6 +140,66 @@ pub struct SecretEntry {
     pub updated_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Environment the value was imported from (e.g. a Doppler config), if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    /// Version number of the current value (starts at 1, bumped on every change).
+    #[serde(default = "first_version")]
+    pub version: u32,
+    /// Prior values, oldest first, bounded by the vault's history limit.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<SecretVersion>,
+    /// Scheduled regeneration (`cred rotate`), if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<RotationPolicy>,
+    /// When the current value stops working. Reset when the value changes: cleared, or
+    /// taken from the `notAfter` of a PEM certificate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+impl SecretEntry {
+    /// True if the entry has a rotation policy and has outlived its maximum age.
+    pub fn rotation_due(&self, now: DateTime<Utc>) -> bool {
+        self.rotation
+            .as_ref()
+            .is_some_and(|p| p.is_due(self.updated_at, now))
+    }
+
+    /// Whether the current value has expired or expires soon.
+    pub fn expiry_status(&self, now: DateTime<Utc>) -> Option<expiry::Status> {
+        expiry::status(self.expires_at, now)
+    }
+
+    /// Snapshot the current value as a history record.
+    fn snapshot(&self) -> SecretVersion {
+        SecretVersion {
+            version: self.version,
+            value: self.value.clone(),
+            format: self.format.clone(),
+            hash: Vault::compute_hash(&self.value),
+            description: self.description.clone(),
+            updated_at: self.updated_at,
+            expires_at: self.expires_at,
+        }
+    }
+
+    /// Move the current value into history and bump the version, keeping at most `limit`
+    /// prior values. Pruned values are zeroized.
+    fn push_history(&mut self, limit: usize) {
+        self.history.push(self.snapshot());
+        self.version += 1;
+        prune_history(&mut self.history, limit);
+    }
+
+    /// Look up a prior value by version number.
+    pub fn history_version(&self, version: u32) -> Option<&SecretVersion> {
+        self.history.iter().find(|v| v.version == version)
+    }
 }
 
 impl Zeroize for SecretEntry {
@@ -97,10 +207,60 @@ impl Zeroize for SecretEntry {
         self.value.zeroize();
         self.hash.zeroize();
         self.description.zeroize();
+        self.environment.zeroize();
+        self.history.iter_mut().for_each(Zeroize::zeroize);
+        self.history.clear();
+    }
+}
+
+/// A prior value of a secret, kept for `secret history` / `secret rollback`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SecretVersion {
+    pub version: u32,
+    pub value: String,
+    #[serde(default)]
+    pub format: SecretFormat,
+    pub hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// When this value was last written (i.e. when it became current).
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Zeroize for SecretVersion {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+        self.hash.zeroize();
+        self.description.zeroize();
+    }
+}
+
+/// Expiry carried by the value itself: a PEM certificate's `notAfter`.
+fn implied_expiry(format: &SecretFormat, value: &str) -> Option<DateTime<Utc>> {
+    match format {
+        SecretFormat::Pem => cert::not_after(value),
+        _ => None,
+    }
+}
+
+/// Drop the oldest entries beyond `limit`, zeroizing their values.
+fn prune_history(history: &mut Vec<SecretVersion>, limit: usize) {
+    if history.len() > limit {
+        let excess = history.len() - limit;
+        history.drain(..excess).for_each(|mut v| v.zeroize());
     }
 }
 
-/// V2 decrypted payload structure.
+/// Last good copy of a vault file, refreshed on every save.
+pub fn backup_path(vault_path: &Path) -> PathBuf {
+    let mut name = vault_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    vault_path.with_file_name(name)
+}
+
+/// V2 decrypted payload structure (unchanged in v3; only the envelope differs).
 #[derive(Serialize, Deserialize, Debug)]
 struct VaultPayloadV2 {
     version: u8,
@@ -108,11 +268,21 @@ struct VaultPayloadV2 {
 }
 
 /// In-memory vault plus file/key context.
+///
+/// A vault may inherit from a parent (e.g. an environment layered over the base
+/// vault): reads fall through to the parent, while writes and `save` only touch
+/// this vault's own file.
 #[derive(Debug, Default)]
 pub struct Vault {
     path: PathBuf,
     key: [u8; 32],
     secrets: HashMap<String, SecretEntry>,
+    parent: Option<Box<Vault>>,
+    history_limit: usize,
+    project_id: Option<Uuid>,
+    /// Loaded from `vault.enc.bak`; the unreadable main file must not replace the backup.
+    recovered: bool,
+    _lock: Option<fsutil::FileLock>,
 }
 
 impl Zeroize for Vault {
@@ -122,6 +292,9 @@ impl Zeroize for Vault {
             v.zeroize();
         });
         self.key.zeroize();
+        if let Some(parent) = self.parent.as_mut() {
+            parent.zeroize();
+        }
     }
 }
 
@@ -133,23 +306,79 @@ impl Drop for Vault {
 
 impl Vault {
     /// Load or initialize a vault from disk, decrypting with the provided 32-byte key.
-    /// Automatically migrates v1 vaults to v2 format in memory.
+    /// Automatically migrates older vaults to the current format in memory.
     pub fn load(vault_path: &Path, key: [u8; 32]) -> Result<Self> {
+        Self::load_for_project(vault_path, key, None)
+    }
+
+    /// Like `load`, but refuses a v3 vault bound to a different project. The project id is
+    /// recorded so the next `save` binds the vault to it.
+    pub fn load_for_project(
+        vault_path: &Path,
+        key: [u8; 32],
+        project_id: Option<Uuid>,
+    ) -> Result<Self> {
+        let lock = match vault_path.parent() {
+            Some(dir) if dir.is_dir() => Some(fsutil::lock(vault_path)?),
+            _ => None,
+        };
         let mut vault = Vault {
             path: vault_path.to_path_buf(),
             key,
             secrets: HashMap::new(),
+            parent: None,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            project_id,
+            recovered: false,
+            _lock: lock,
         };
 
         if !vault_path.exists() {
             return Ok(vault);
         }
 
-        let content = fs::read_to_string(vault_path).context("Failed to read vault.enc")?;
+        let (secrets, found_id) = match Self::read_file(vault_path, &key) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                let backup = backup_path(vault_path);
+                match Self::read_file(&backup, &key) {
+                    Ok(loaded) => {
+                        eprintln!(
+                            "Warning: {} is unreadable ({:#}); loaded the last good copy from {}",
+                            vault_path.display(),
+                            err,
+                            backup.display()
+                        );
+                        vault.recovered = true;
+                        loaded
+                    }
+                    _ => return Err(err),
+                }
+            }
+        };
+        match (project_id, found_id) {
+            (Some(expected), Some(found)) if expected != found => bail!(
+                "Vault belongs to project {}, not this project ({})",
+                found,
+                expected
+            ),
+            (None, found) => vault.project_id = found,
+            _ => {}
+        }
+        vault.secrets = secrets;
+        Ok(vault)
+    }
+
+    /// Decrypt one vault file, returning its secrets and the project id in its header.
+    fn read_file(
+        path: &Path,
+        key: &[u8; 32],
+    ) -> Result<(HashMap<String, SecretEntry>, Option<Uuid>)> {
+        let content = fs::read_to_string(path).context("Failed to read vault.enc")?;
         let file_data: EncryptedVaultFile =
             serde_json::from_str(&content).context("Failed to parse vault structure")?;
 
-        let cipher = ChaCha20Poly1305::new(&key.into());
+        let cipher = ChaCha20Poly1305::new(key.into());
 
         let nonce_bytes = BASE64
             .decode(&file_data.nonce)
@@ -160,18 +389,56 @@ impl Vault {
 
         let nonce = Nonce::from_slice(&nonce_bytes);
 
+        if file_data.version > CURRENT_VERSION {
+            bail!(
+                "Unsupported vault version: {}. Please upgrade cred.",
+                file_data.version
+            );
+        }
+        if file_data.version >= 3
+            && (file_data.cipher.as_deref() != Some(VAULT_CIPHER)
+                || file_data.kdf.as_deref() != Some(VAULT_KDF))
+        {
+            bail!(
+                "Unsupported vault cipher/KDF: {}/{}",
+                file_data.cipher.as_deref().unwrap_or("?"),
+                file_data.kdf.as_deref().unwrap_or("?")
+            );
+        }
+
+        let payload = Payload {
+            msg: ciphertext.as_ref(),
+            aad: &file_data.aad(),
+        };
         let plaintext = cipher
-            .decrypt(nonce, ciphertext.as_ref())
+            .decrypt(nonce, payload)
             .map_err(|_| anyhow::anyhow!("Decryption failed. Data corrupted or wrong key."))?;
 
         let secrets = match file_data.version {
             1 => Self::migrate_v1_to_v2(&plaintext)?,
-            2 => Self::parse_v2(&plaintext)?,
-            v => bail!("Unsupported vault version: {}. Please upgrade cred.", v),
+            _ => Self::parse_v2(&plaintext)?,
         };
 
-        vault.secrets = secrets;
-        Ok(vault)
+        Ok((secrets, file_data.project_id))
+    }
+
+    /// Layer this vault over `parent`; keys missing here are read from the parent.
+    pub fn with_parent(mut self, parent: Vault) -> Self {
+        self.parent = Some(Box::new(parent));
+        self
+    }
+
+    /// Keep at most `limit` prior values per secret. Existing histories are trimmed
+    /// (and the dropped values zeroized) on the next `save`.
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history_limit = limit;
+        self
+    }
+
+    /// Switch the key used by subsequent `save` calls (for master key rotation).
+    pub fn rekey(&mut self, key: [u8; 32]) {
+        self.key.zeroize();
+        self.key = key;
     }
 
     /// Migrate v1 (bare strings) to v2 (SecretEntry).
@@ -191,6 +458,11 @@ impl Vault {
                     created_at: now,
                     updated_at: now,
                     description: None,
+                    environment: None,
+                    version: 1,
+                    history: Vec::new(),
+                    rotation: None,
+                    expires_at: None,
                 };
                 (k, entry)
             })
@@ -199,7 +471,7 @@ impl Vault {
         Ok(migrated)
     }
 
-    /// Parse v2 payload directly.
+    /// Parse a v2/v3 payload directly.
     fn parse_v2(plaintext: &[u8]) -> Result<HashMap<String, SecretEntry>> {
         let payload: VaultPayloadV2 =
             serde_json::from_slice(plaintext).context("Failed to parse v2 payload")?;
@@ -231,12 +503,11 @@ impl Vault {
         }
 
         // JSON detection — must actually parse, not just look like JSON
-        if (trimmed.starts_with('{') && trimmed.ends_with('}'))
-            || (trimmed.starts_with('[') && trimmed.ends_with(']'))
+        if ((trimmed.starts_with('{') && trimmed.ends_with('}'))
+            || (trimmed.starts_with('[') && trimmed.ends_with(']')))
+            && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
         {
-            if serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
-                return SecretFormat::Json;
-            }
+            return SecretFormat::Json;
         }
 
         // Base64 detection — strict validation only
@@ -267,7 +538,7 @@ impl Vault {
         }
 
         // Base64 output length must be divisible by 4
-        if s.len() % 4 != 0 {
+        if !s.len().is_multiple_of(4) {
             return false;
         }
 
@@ -301,13 +572,14 @@ impl Vault {
         format!("{:x}", hasher.finalize())
     }
 
-    /// Encrypt and persist the current secrets to `vault.enc` (always as v2).
+    /// Encrypt and persist the current secrets to `vault.enc` (always as v3).
     /// Computes and stores value hashes for change detection.
     pub fn save(&self) -> Result<()> {
         // Clone secrest and compute hashes before persisting
         let mut secrets = self.secrets.clone();
         for entry in secrets.values_mut() {
             entry.hash = Some(Self::compute_hash(&entry.value));
+            prune_history(&mut entry.history, self.history_limit);
         }
         let payload = VaultPayloadV2 {
             version: CURRENT_VERSION,
@@ -315,54 +587,74 @@ impl Vault {
         };
         let plaintext = serde_json::to_vec(&payload)?;
 
+        let mut file_data = EncryptedVaultFile {
+            version: CURRENT_VERSION,
+            project_id: self.project_id,
+            kdf: Some(VAULT_KDF.to_string()),
+            cipher: Some(VAULT_CIPHER.to_string()),
+            nonce: String::new(),
+            ciphertext: String::new(),
+        };
+
         let cipher = ChaCha20Poly1305::new(&self.key.into());
         let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: plaintext.as_ref(),
+            aad: &file_data.aad(),
+        };
         let ciphertext = cipher
-            .encrypt(&nonce, plaintext.as_ref())
+            .encrypt(&nonce, payload)
             .map_err(|e| anyhow::anyhow!("Encryption failed: {:?}", e))?;
-
-        let file_data = EncryptedVaultFile {
-            version: CURRENT_VERSION,
-            nonce: BASE64.encode(nonce),
-            ciphertext: BASE64.encode(ciphertext),
-        };
+        file_data.nonce = BASE64.encode(nonce);
+        file_data.ciphertext = BASE64.encode(ciphertext);
 
         let json = serde_json::to_string_pretty(&file_data)?;
-        fs::write(&self.path, json).context("Failed to write to vault.enc")?;
+        let _lock = fsutil::lock(&self.path)?;
+        if self.path.exists() && !self.recovered {
+            let current = fs::read(&self.path).context("Failed to read vault.enc")?;
+            fsutil::write_atomic(&backup_path(&self.path), &current)?;
+        }
+        fsutil::write_atomic(&self.path, json.as_bytes())?;
         Ok(())
     }
 
     /// Check if a secret's value has changed since last save.
     pub fn is_dirty(&self, key: &str) -> bool {
-        match self.secrets.get(key) {
-            Some(entry) => match &entry.hash {
-                Some(h) => Self::compute_hash(&entry.value) != *h,
-                None => true,
-            },
+        match self.get_entry(key) {
+            Some(entry) => Self::entry_is_dirty(entry),
             None => false,
         }
     }
 
     /// Get all keys that have changed since last save.
     pub fn dirty_keys(&self) -> Vec<&str> {
-        self.secrets
-            .iter()
-            .filter(|(_, entry)| match &entry.hash {
-                Some(h) => Self::compute_hash(&entry.value) != *h,
-                None => true,
-            })
+        self.effective_entries()
+            .into_iter()
+            .filter(|(_, entry)| Self::entry_is_dirty(entry))
             .map(|(k, _)| k.as_str())
             .collect()
     }
 
+    fn entry_is_dirty(entry: &SecretEntry) -> bool {
+        match &entry.hash {
+            Some(h) => Self::compute_hash(&entry.value) != *h,
+            None => true,
+        }
+    }
+
     /// Insert or overwrite a secret key/value in memory (not persisted until `save`).
     /// Automatically detects format and updates timestamps.
     pub fn set(&mut self, key: &str, value: &str) {
         let now = Utc::now();
         let format = Self::detect_format(value);
+        let limit = self.history_limit;
 
         match self.secrets.get_mut(key) {
             Some(entry) => {
+                if entry.value != value {
+                    entry.push_history(limit);
+                    entry.expires_at = implied_expiry(&format, value);
+                }
                 entry.value = value.to_string();
                 entry.format = format;
                 entry.updated_at = now;
@@ -370,6 +662,7 @@ impl Vault {
                 entry.hash = None;
             }
             None => {
+                let expires_at = implied_expiry(&format, value);
                 self.secrets.insert(
                     key.to_string(),
                     SecretEntry {
@@ -379,6 +672,11 @@ impl Vault {
                         created_at: now,
                         updated_at: now,
                         description: None,
+                        environment: None,
+                        version: 1,
+                        history: Vec::new(),
+                        rotation: None,
+                        expires_at,
                     },
                 );
             }
@@ -394,9 +692,14 @@ impl Vault {
         description: Option<String>,
     ) {
         let now = Utc::now();
+        let limit = self.history_limit;
 
         match self.secrets.get_mut(key) {
             Some(entry) => {
+                if entry.value != value {
+                    entry.push_history(limit);
+                    entry.expires_at = implied_expiry(&format, value);
+                }
                 entry.value = value.to_string();
                 entry.format = format;
                 entry.description = description;
@@ -404,6 +707,7 @@ impl Vault {
                 entry.hash = None;
             }
             None => {
+                let expires_at = implied_expiry(&format, value);
                 self.secrets.insert(
                     key.to_string(),
                     SecretEntry {
@@ -413,20 +717,36 @@ impl Vault {
                         created_at: now,
                         updated_at: now,
                         description,
+                        environment: None,
+                        version: 1,
+                        history: Vec::new(),
+                        rotation: None,
+                        expires_at,
                     },
                 );
             }
         }
     }
 
-    /// Fetch a secret value by key from memory.
+    /// Fetch a secret value by key from memory (falling back to the parent vault).
     pub fn get(&self, key: &str) -> Option<&String> {
-        self.secrets.get(key).map(|e| &e.value)
+        self.get_entry(key).map(|e| &e.value)
     }
 
-    /// Fetch the full secret entry by key from memory.
+    /// Fetch the full secret entry by key from memory (falling back to the parent vault).
     pub fn get_entry(&self, key: &str) -> Option<&SecretEntry> {
-        self.secrets.get(key)
+        self.secrets
+            .get(key)
+            .or_else(|| self.parent.as_ref().and_then(|p| p.get_entry(key)))
+    }
+
+    /// True if `key` is only present in a parent vault (not overridden here).
+    pub fn is_inherited(&self, key: &str) -> bool {
+        !self.secrets.contains_key(key)
+            && self
+                .parent
+                .as_ref()
+                .is_some_and(|p| p.get_entry(key).is_some())
     }
 
     /// Remove a secret, returning the prior value if present (not persisted until `save`).
@@ -439,19 +759,73 @@ impl Vault {
         self.secrets.remove(key)
     }
 
-    /// Borrow the in-memory secrets map (key → value only, for backward compatibility).
+    /// Copy the effective secrets map (key → value only, for backward compatibility),
+    /// including values inherited from the parent vault.
     pub fn list(&self) -> HashMap<String, String> {
-        self.secrets
-            .iter()
+        self.effective_entries()
+            .into_iter()
             .map(|(k, e)| (k.clone(), e.value.clone()))
             .collect()
     }
 
-    /// Borrow the full in-memory secrets map with all metadata.
+    /// Borrow this vault's own secrets map with all metadata (excludes inherited keys).
     pub fn list_entries(&self) -> &HashMap<String, SecretEntry> {
         &self.secrets
     }
 
+    /// All entries visible through this vault: inherited entries overridden by own ones.
+    pub fn effective_entries(&self) -> HashMap<&String, &SecretEntry> {
+        let mut entries = self
+            .parent
+            .as_ref()
+            .map(|p| p.effective_entries())
+            .unwrap_or_default();
+        entries.extend(self.secrets.iter());
+        entries
+    }
+
+    /// Effective entries for `keys` (or all of them), sorted by key; unknown keys are an error.
+    pub fn select_entries(&self, keys: Option<&[String]>) -> Result<Vec<(&String, &SecretEntry)>> {
+        let entries = self.effective_entries();
+        let mut selected = match keys {
+            Some(keys) => keys
+                .iter()
+                .map(|k| {
+                    entries
+                        .get_key_value(k)
+                        .map(|(k, e)| (*k, *e))
+                        .with_context(|| format!("Secret '{}' not found", k))
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => entries.into_iter().collect(),
+        };
+        selected.sort_by(|a, b| a.0.cmp(b.0));
+        Ok(selected)
+    }
+
+    /// Restore a prior version of a secret. The current value is pushed onto the
+    /// history first, so a rollback can itself be rolled back.
+    pub fn rollback(&mut self, key: &str, version: u32) -> Result<u32> {
+        let limit = self.history_limit;
+        let entry = self
+            .secrets
+            .get_mut(key)
+            .ok_or_else(|| anyhow::anyhow!("Secret '{}' not found", key))?;
+        let mut target = entry.history_version(version).cloned().ok_or_else(|| {
+            anyhow::anyhow!("Version {} of '{}' not found in history", version, key)
+        })?;
+
+        entry.push_history(limit);
+        entry.value = std::mem::take(&mut target.value);
+        entry.format = target.format.clone();
+        entry.description = target.description.take();
+        entry.expires_at = target.expires_at;
+        target.zeroize();
+        entry.updated_at = Utc::now();
+        entry.hash = None;
+        Ok(entry.version)
+    }
+
     /// Update the description for an existing secret.
     pub fn set_description(&mut self, key: &str, description: Option<String>) -> bool {
         if let Some(entry) = self.secrets.get_mut(key) {
@@ -463,6 +837,66 @@ impl Vault {
         }
     }
 
+    /// Record the source environment for an existing secret.
+    pub fn set_environment(&mut self, key: &str, environment: Option<String>) -> bool {
+        if let Some(entry) = self.secrets.get_mut(key) {
+            entry.environment = environment;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Attach (or with `None`, clear) the rotation policy of an existing secret.
+    pub fn set_rotation(&mut self, key: &str, rotation: Option<RotationPolicy>) -> bool {
+        if let Some(entry) = self.secrets.get_mut(key) {
+            entry.rotation = rotation;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Set (or with `None`, clear) when the current value of an existing secret expires.
+    pub fn set_expiry(&mut self, key: &str, expires_at: Option<DateTime<Utc>>) -> bool {
+        if let Some(entry) = self.secrets.get_mut(key) {
+            entry.expires_at = expires_at;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Override the detected format of an existing secret.
+    pub fn set_format(&mut self, key: &str, format: SecretFormat) -> bool {
+        if let Some(entry) = self.secrets.get_mut(key) {
+            entry.format = format;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Restore original timestamps on an existing secret (e.g. when re-importing an export).
+    pub fn set_timestamps(
+        &mut self,
+        key: &str,
+        created_at: Option<DateTime<Utc>>,
+        updated_at: Option<DateTime<Utc>>,
+    ) -> bool {
+        if let Some(entry) = self.secrets.get_mut(key) {
+            if let Some(created_at) = created_at {
+                entry.created_at = created_at;
+            }
+            if let Some(updated_at) = updated_at {
+                entry.updated_at = updated_at;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
     /// Update the hash for an existing secret.
     #[allow(dead_code)]
     pub fn set_hash(&mut self, key: &str, hash: Option<String>) -> bool {